@@ -1,7 +1,11 @@
-use cosmwasm_std::{to_binary, Binary};
+use cosmwasm_std::{from_binary, from_slice, to_binary, to_vec, Binary, Event, Uint128};
+use cw_utils::Expiration;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::error::ErrorCode;
+use crate::ContractError;
+
 /// IBC ACK. See:
 /// https://github.com/cosmos/cosmos-sdk/blob/f999b1ff05a4db4a338a855713864497bedd4396/proto/ibc/core/channel/v1/channel.proto#L141-L147
 #[derive(Serialize, Deserialize, JsonSchema)]
@@ -11,12 +15,209 @@ pub enum Ack {
     Error(String),
 }
 
-pub fn make_ack_success() -> Binary {
-    let res = Ack::Result(b"1".into());
+/// Reason a packet failed, JSON encoded into the string of `Ack::Error` so
+/// the ack stays readable by anything expecting free-form text.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AckError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+/// State on the receiving chain after an `IbcExecuteMsg` was executed,
+/// carried back to the sender in a successful ack. Balances are the ones of
+/// the account acting for the sender unless named otherwise.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PacketResult {
+    Transfer {
+        recipient: String,
+        /// Balance of the recipient.
+        balance: Uint128,
+    },
+    Burn {
+        balance: Uint128,
+        total_supply: Uint128,
+    },
+    Send {
+        contract: String,
+        balance: Uint128,
+    },
+    Mint {
+        recipient: String,
+        /// Balance of the recipient.
+        balance: Uint128,
+        total_supply: Uint128,
+    },
+    IncreaseAllowance {
+        spender: String,
+        allowance: Uint128,
+        expires: Expiration,
+    },
+    DecreaseAllowance {
+        spender: String,
+        allowance: Uint128,
+        expires: Expiration,
+    },
+    TransferFrom {
+        owner: String,
+        recipient: String,
+        owner_balance: Uint128,
+        /// Allowance left to the sender.
+        allowance: Uint128,
+    },
+    SendFrom {
+        owner: String,
+        contract: String,
+        owner_balance: Uint128,
+        /// Allowance left to the sender.
+        allowance: Uint128,
+    },
+    BurnFrom {
+        owner: String,
+        owner_balance: Uint128,
+        /// Allowance left to the sender.
+        allowance: Uint128,
+        total_supply: Uint128,
+    },
+    ProposeMinter {
+        pending_minter: String,
+        expiry: Expiration,
+    },
+    UpdateCap {
+        cap: Option<Uint128>,
+    },
+}
+
+impl PacketResult {
+    /// Event emitted by the sending contract when the ack comes back.
+    pub fn into_event(self) -> Event {
+        let event = Event::new("packet_result");
+        match self {
+            PacketResult::Transfer { recipient, balance } => event
+                .add_attribute("action", "transfer")
+                .add_attribute("recipient", recipient)
+                .add_attribute("balance", balance),
+            PacketResult::Burn {
+                balance,
+                total_supply,
+            } => event
+                .add_attribute("action", "burn")
+                .add_attribute("balance", balance)
+                .add_attribute("total_supply", total_supply),
+            PacketResult::Send { contract, balance } => event
+                .add_attribute("action", "send")
+                .add_attribute("contract", contract)
+                .add_attribute("balance", balance),
+            PacketResult::Mint {
+                recipient,
+                balance,
+                total_supply,
+            } => event
+                .add_attribute("action", "mint")
+                .add_attribute("recipient", recipient)
+                .add_attribute("balance", balance)
+                .add_attribute("total_supply", total_supply),
+            PacketResult::IncreaseAllowance {
+                spender,
+                allowance,
+                expires,
+            } => event
+                .add_attribute("action", "increase_allowance")
+                .add_attribute("spender", spender)
+                .add_attribute("allowance", allowance)
+                .add_attribute("expires", expires.to_string()),
+            PacketResult::DecreaseAllowance {
+                spender,
+                allowance,
+                expires,
+            } => event
+                .add_attribute("action", "decrease_allowance")
+                .add_attribute("spender", spender)
+                .add_attribute("allowance", allowance)
+                .add_attribute("expires", expires.to_string()),
+            PacketResult::TransferFrom {
+                owner,
+                recipient,
+                owner_balance,
+                allowance,
+            } => event
+                .add_attribute("action", "transfer_from")
+                .add_attribute("owner", owner)
+                .add_attribute("recipient", recipient)
+                .add_attribute("owner_balance", owner_balance)
+                .add_attribute("allowance", allowance),
+            PacketResult::SendFrom {
+                owner,
+                contract,
+                owner_balance,
+                allowance,
+            } => event
+                .add_attribute("action", "send_from")
+                .add_attribute("owner", owner)
+                .add_attribute("contract", contract)
+                .add_attribute("owner_balance", owner_balance)
+                .add_attribute("allowance", allowance),
+            PacketResult::BurnFrom {
+                owner,
+                owner_balance,
+                allowance,
+                total_supply,
+            } => event
+                .add_attribute("action", "burn_from")
+                .add_attribute("owner", owner)
+                .add_attribute("owner_balance", owner_balance)
+                .add_attribute("allowance", allowance)
+                .add_attribute("total_supply", total_supply),
+            PacketResult::ProposeMinter {
+                pending_minter,
+                expiry,
+            } => event
+                .add_attribute("action", "propose_minter")
+                .add_attribute("pending_minter", pending_minter)
+                .add_attribute("expiry", expiry.to_string()),
+            PacketResult::UpdateCap { cap } => {
+                event.add_attribute("action", "update_cap").add_attribute(
+                    "cap",
+                    cap.map(|cap| cap.to_string())
+                        .unwrap_or_else(|| "None".to_string()),
+                )
+            }
+        }
+    }
+}
+
+pub fn make_ack_success(result: &PacketResult) -> Binary {
+    let res = Ack::Result(to_binary(result).unwrap());
+    to_binary(&res).unwrap()
+}
+
+/// Success ack of the ICS-20 transfer module.
+pub fn make_ack_ics20_success() -> Binary {
+    let res = Ack::Result(Binary::from(vec![1]));
     to_binary(&res).unwrap()
 }
 
-pub fn make_ack_fail(err: String) -> Binary {
-    let res = Ack::Error(err);
+pub fn make_ack_fail(err: &ContractError) -> Binary {
+    let err = AckError {
+        code: err.code(),
+        message: err.to_string(),
+    };
+    let res = Ack::Error(String::from_utf8(to_vec(&err).unwrap()).unwrap());
     to_binary(&res).unwrap()
 }
+
+/// Decodes the reason of an error ack. Errors from counterparties that don't
+/// send codes are kept as their message with an `Unknown` code.
+pub fn parse_ack_error(err: &str) -> AckError {
+    from_slice(err.as_bytes()).unwrap_or_else(|_| AckError {
+        code: ErrorCode::Unknown,
+        message: err.to_string(),
+    })
+}
+
+/// Decodes the result of a successful ack. Counterparties running an older
+/// version acknowledge with `1` and carry no result.
+pub fn parse_ack_result(data: &Binary) -> Option<PacketResult> {
+    from_binary(data).ok()
+}
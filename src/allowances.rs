@@ -1,9 +1,10 @@
 use cosmwasm_std::{
-    attr, Addr, Binary, BlockInfo, Deps, DepsMut, Env, IbcReceiveResponse, MessageInfo, Response,
-    StdError, StdResult, Storage, Uint128,
+    attr, Addr, Binary, BlockInfo, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
+    Storage, Uint128,
 };
 use cw20::{AllowanceResponse, Cw20ReceiveMsg, Expiration};
 
+use crate::contract::ensure_burnable;
 use crate::error::ContractError;
 use crate::state::{ALLOWANCES, ALLOWANCES_SPENDER, BALANCES, TOKEN_INFO};
 
@@ -14,7 +15,6 @@ pub fn execute_increase_allowance(
     spender: String,
     amount: Uint128,
     expires: Option<Expiration>,
-    channel: String,
 ) -> Result<Response, ContractError> {
     let spender_addr = deps.api.addr_validate(&spender)?;
     if spender_addr == info.sender {
@@ -32,16 +32,8 @@ pub fn execute_increase_allowance(
         val.allowance += amount;
         Ok(val)
     };
-    ALLOWANCES.update(
-        deps.storage,
-        (channel.clone(), &info.sender, &spender_addr),
-        update_fn,
-    )?;
-    ALLOWANCES_SPENDER.update(
-        deps.storage,
-        (channel.clone(), &spender_addr, &info.sender),
-        update_fn,
-    )?;
+    ALLOWANCES.update(deps.storage, (&info.sender, &spender_addr), update_fn)?;
+    ALLOWANCES_SPENDER.update(deps.storage, (&spender_addr, &info.sender), update_fn)?;
 
     let res = Response::new().add_attributes(vec![
         attr("action", "increase_allowance"),
@@ -59,21 +51,20 @@ pub fn execute_decrease_allowance(
     spender: String,
     amount: Uint128,
     expires: Option<Expiration>,
-    channel: String,
 ) -> Result<Response, ContractError> {
     let spender_addr = deps.api.addr_validate(&spender)?;
     if spender_addr == info.sender {
         return Err(ContractError::CannotSetOwnAccount {});
     }
 
-    let key = (channel, &info.sender, &spender_addr);
+    let key = (&info.sender, &spender_addr);
 
-    fn reverse<'a>(t: (String, &'a Addr, &'a Addr)) -> (String, &'a Addr, &'a Addr) {
-        (t.0, t.2, t.1)
+    fn reverse<'a>(t: (&'a Addr, &'a Addr)) -> (&'a Addr, &'a Addr) {
+        (t.1, t.0)
     }
 
     // load value and delete if it hits 0, or update otherwise
-    let mut allowance = ALLOWANCES.load(deps.storage, key.clone())?;
+    let mut allowance = ALLOWANCES.load(deps.storage, key)?;
     if amount < allowance.allowance {
         // update the new amount
         allowance.allowance = allowance
@@ -86,11 +77,11 @@ pub fn execute_decrease_allowance(
             }
             allowance.expires = exp;
         }
-        ALLOWANCES.save(deps.storage, key.clone(), &allowance)?;
-        ALLOWANCES_SPENDER.save(deps.storage, reverse(key.clone()), &allowance)?;
+        ALLOWANCES.save(deps.storage, key, &allowance)?;
+        ALLOWANCES_SPENDER.save(deps.storage, reverse(key), &allowance)?;
     } else {
-        ALLOWANCES.remove(deps.storage, key.clone());
-        ALLOWANCES_SPENDER.remove(deps.storage, reverse(key.clone()));
+        ALLOWANCES.remove(deps.storage, key);
+        ALLOWANCES_SPENDER.remove(deps.storage, reverse(key));
     }
 
     let res = Response::new().add_attributes(vec![
@@ -109,7 +100,6 @@ pub fn deduct_allowance(
     spender: &Addr,
     block: &BlockInfo,
     amount: Uint128,
-    channel: String,
 ) -> Result<AllowanceResponse, ContractError> {
     let update_fn = |current: Option<AllowanceResponse>| -> _ {
         match current {
@@ -128,8 +118,39 @@ pub fn deduct_allowance(
             None => Err(ContractError::NoAllowance {}),
         }
     };
-    ALLOWANCES.update(storage, (channel.clone(), owner, spender), update_fn)?;
-    ALLOWANCES_SPENDER.update(storage, (channel.clone(), spender, owner), update_fn)
+    ALLOWANCES.update(storage, (owner, spender), update_fn)?;
+    ALLOWANCES_SPENDER.update(storage, (spender, owner), update_fn)
+}
+
+/// Gives back `amount` deducted from the allowance of `spender`, when what it
+/// paid for is rolled back.
+pub fn restore_allowance(
+    storage: &mut dyn Storage,
+    owner: &Addr,
+    spender: &Addr,
+    amount: Uint128,
+) -> StdResult<()> {
+    let update_fn = |current: Option<AllowanceResponse>| -> StdResult<_> {
+        let mut a = current.unwrap_or_default();
+        a.allowance += amount;
+        Ok(a)
+    };
+    ALLOWANCES.update(storage, (owner, spender), update_fn)?;
+    ALLOWANCES_SPENDER.update(storage, (spender, owner), update_fn)?;
+    Ok(())
+}
+
+/// Balance of `owner` left after spending `amount`, failing if it can't
+/// cover it. Checked before the allowance is deducted, as nothing may be
+/// written for packets that fail: they are answered with an error ack
+/// instead of being reverted.
+fn remaining_balance(
+    storage: &dyn Storage,
+    owner: &Addr,
+    amount: Uint128,
+) -> Result<Uint128, ContractError> {
+    let balance = BALANCES.may_load(storage, owner)?.unwrap_or_default();
+    Ok(balance.checked_sub(amount).map_err(StdError::overflow)?)
 }
 
 pub fn execute_transfer_from(
@@ -139,31 +160,18 @@ pub fn execute_transfer_from(
     owner: String,
     recipient: String,
     amount: Uint128,
-    channel: String,
 ) -> Result<Response, ContractError> {
     let rcpt_addr = deps.api.addr_validate(&recipient)?;
     let owner_addr = deps.api.addr_validate(&owner)?;
 
-    // deduct allowance before doing anything else have enough allowance
-    deduct_allowance(
-        deps.storage,
-        &owner_addr,
-        &info.sender,
-        &env.block,
-        amount,
-        channel.clone(),
-    )?;
+    // check the balance first, the allowance is only deducted if the owner has enough
+    let remaining = remaining_balance(deps.storage, &owner_addr, amount)?;
+    deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
 
+    BALANCES.save(deps.storage, &owner_addr, &remaining)?;
     BALANCES.update(
         deps.storage,
-        (channel.clone(), &owner_addr),
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
-    )?;
-    BALANCES.update(
-        deps.storage,
-        (channel.clone(), &rcpt_addr),
+        &rcpt_addr,
         |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
     )?;
 
@@ -183,33 +191,21 @@ pub fn execute_burn_from(
     info: MessageInfo,
     owner: String,
     amount: Uint128,
-    channel: String,
 ) -> Result<Response, ContractError> {
+    ensure_burnable(deps.storage)?;
     let owner_addr = deps.api.addr_validate(&owner)?;
 
-    // deduct allowance before doing anything else have enough allowance
-    deduct_allowance(
-        deps.storage,
-        &owner_addr,
-        &info.sender,
-        &env.block,
-        amount,
-        channel.clone(),
-    )?;
+    // check the balance first, the allowance is only deducted if the owner has enough
+    let remaining = remaining_balance(deps.storage, &owner_addr, amount)?;
+    deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
 
     // lower balance
-    BALANCES.update(
-        deps.storage,
-        (channel.clone(), &owner_addr),
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
-    )?;
+    BALANCES.save(deps.storage, &owner_addr, &remaining)?;
 
-    let mut token_info = TOKEN_INFO.load(deps.storage, channel.clone())?;
-    token_info.total_supply = token_info.total_supply - amount;
+    let mut token_info = TOKEN_INFO.load(deps.storage)?;
+    token_info.total_supply -= amount;
     // reduce total_supply
-    TOKEN_INFO.save(deps.storage, channel.clone(), &token_info)?;
+    TOKEN_INFO.save(deps.storage, &token_info)?;
 
     let res = Response::new().add_attributes(vec![
         attr("action", "burn_from"),
@@ -228,32 +224,19 @@ pub fn execute_send_from(
     contract: String,
     amount: Uint128,
     msg: Binary,
-    channel: String,
 ) -> Result<Response, ContractError> {
     let rcpt_addr = deps.api.addr_validate(&contract)?;
     let owner_addr = deps.api.addr_validate(&owner)?;
 
-    // deduct allowance before doing anything else have enough allowance
-    deduct_allowance(
-        deps.storage,
-        &owner_addr,
-        &info.sender,
-        &env.block,
-        amount,
-        channel.clone(),
-    )?;
+    // check the balance first, the allowance is only deducted if the owner has enough
+    let remaining = remaining_balance(deps.storage, &owner_addr, amount)?;
+    deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
 
     // move the tokens to the contract
+    BALANCES.save(deps.storage, &owner_addr, &remaining)?;
     BALANCES.update(
         deps.storage,
-        (channel.clone(), &owner_addr),
-        |balance: Option<Uint128>| -> StdResult<_> {
-            Ok(balance.unwrap_or_default().checked_sub(amount)?)
-        },
-    )?;
-    BALANCES.update(
-        deps.storage,
-        (channel, &rcpt_addr),
+        &rcpt_addr,
         |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
     )?;
 
@@ -277,16 +260,11 @@ pub fn execute_send_from(
     Ok(res)
 }
 
-pub fn query_allowance(
-    deps: Deps,
-    owner: String,
-    spender: String,
-    channel: String,
-) -> StdResult<AllowanceResponse> {
+pub fn query_allowance(deps: Deps, owner: String, spender: String) -> StdResult<AllowanceResponse> {
     let owner_addr = deps.api.addr_validate(&owner)?;
     let spender_addr = deps.api.addr_validate(&spender)?;
     let allowance = ALLOWANCES
-        .may_load(deps.storage, (channel, &owner_addr, &spender_addr))?
+        .may_load(deps.storage, (&owner_addr, &spender_addr))?
         .unwrap_or_default();
     Ok(allowance)
 }
@@ -1,11 +1,9 @@
-use std::sync::mpsc::channel;
-
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::Order::Ascending;
 use cosmwasm_std::{
-    to_binary, Binary, Deps, DepsMut, Env, IbcMsg, IbcTimeout, MessageInfo, Response, StdError,
-    StdResult, Uint128,
+    to_binary, Addr, Binary, Deps, DepsMut, Env, IbcMsg, IbcTimeout, MessageInfo, Reply, Response,
+    StdError, StdResult, Storage, Uint128,
 };
 
 use cw2::set_contract_version;
@@ -13,20 +11,39 @@ use cw20::{
     BalanceResponse, Cw20Coin, Cw20ReceiveMsg, DownloadLogoResponse, EmbeddedLogo, Logo, LogoInfo,
     MarketingInfoResponse, MinterResponse, TokenInfoResponse,
 };
-use cw_utils::ensure_from_older_version;
+use cw_storage_plus::Map;
+use cw_utils::{ensure_from_older_version, Expiration};
 
 use crate::allowances::{
     execute_burn_from, execute_decrease_allowance, execute_increase_allowance, execute_send_from,
     execute_transfer_from, query_allowance,
 };
-use crate::enumerable::{query_owner_allowances, query_spender_allowances};
+use crate::enumerable::{
+    query_all_accounts, query_all_balances, query_list_channels, query_owner_allowances,
+    query_spender_allowances,
+};
 use crate::error::ContractError;
+use crate::ibc::{
+    is_control_channel, make_packet, remote_account, reply_ack_callback, reply_send,
+    reply_transfer_callback, update_stats, ACK_CALLBACK_REPLY_ID, SEND_REPLY_ID,
+    TRANSFER_CALLBACK_REPLY_ID,
+};
+use crate::ics20::{ics20_denom, is_ics20_channel, query_denom_trace, Ics20Packet};
 use crate::msg::{
-    ExecuteMsg, GetCountResponse, IbcExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg,
+    Callback, ChannelAmount, ExecuteMsg, IbcExecuteMsg, InstantiateMsg, MigrateMsg, PauseScope,
+    PendingMinterResponse, QueryMsg, SupplyBreakdownResponse,
+};
+use crate::pause::{
+    ensure_not_paused, ensure_send_not_paused, is_anything_paused, is_guardian, query_pause_status,
+    set_paused,
 };
+use crate::rate_limit::{self, query_rate_limit_usage, Flow};
 use crate::state::{
-    Chains, MinterData, TokenInfo, ALLOWANCES, ALLOWANCES_SPENDER, BALANCES, CHAINS,
-    CONNECTION_COUNTS, LOGO, MARKETING_INFO, TOKEN_INFO,
+    Chains, ChannelStatus, Controller, InFlightPacket, MainDeployment, MinterData, Ownership,
+    PendingMinter, RateLimit, RateLimits, TokenInfo, ALLOWANCES, ALLOWANCES_SPENDER, BALANCES,
+    CHAINS, CHANNELS, CHANNEL_ESCROW, CHANNEL_OUTSTANDING, CHANNEL_STATS, CONTROLLER, GUARDIAN,
+    IN_FLIGHT, IS_MAIN, LOGO, MAIN_DEPLOYMENT, MARKETING_INFO, OWNERSHIP, PENDING_MINTER,
+    RATE_LIMITS, REMOTE_ALLOWANCES, REMOTE_DENOMS, TOKEN_INFO, VOUCHER_SUPPLY,
 };
 
 // version info for migration info
@@ -35,6 +52,9 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 const LOGO_SIZE_CAP: usize = 5 * 1024;
 
+/// Packet timeout used by `TransferRemote` when none is given.
+pub const DEFAULT_TIMEOUT_SECONDS: u64 = 300;
+
 /// Checks if data starts with XML preamble
 fn verify_xml_preamble(data: &[u8]) -> Result<(), ContractError> {
     // The easiest way to perform this check would be just match on regex, however regex
@@ -100,15 +120,25 @@ fn verify_logo(logo: &Logo) -> Result<(), ContractError> {
 pub fn instantiate(
     mut deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: InstantiateMsg,
-    channel: String,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     // check valid token info
     msg.validate()?;
+    // satellites only ever hold vouchers for tokens escrowed on the main deployment
+    if !msg.is_main && !msg.initial_balances.is_empty() {
+        return Err(ContractError::NotMainDeployment {});
+    }
+    IS_MAIN.save(deps.storage, &msg.is_main)?;
+    if let Some(main_deployment) = &msg.main_deployment {
+        if msg.is_main {
+            return Err(ContractError::NotSatellite {});
+        }
+        MAIN_DEPLOYMENT.save(deps.storage, main_deployment)?;
+    }
     // create initial accounts
-    let total_supply = create_accounts(&mut deps, &msg.initial_balances, channel.clone())?;
+    let total_supply = create_accounts(&mut deps, &msg.initial_balances)?;
 
     if let Some(limit) = msg.get_cap() {
         if total_supply > limit {
@@ -132,11 +162,22 @@ pub fn instantiate(
         total_supply,
         mint,
     };
-    TOKEN_INFO.save(deps.storage, channel.clone(), &data)?;
+    TOKEN_INFO.save(deps.storage, &data)?;
     let chains = Chains {
         other_chains: msg.other_chains,
     };
     CHAINS.save(deps.storage, &chains)?;
+    let owner = match msg.owner {
+        Some(owner) => deps.api.addr_validate(&owner)?,
+        None => info.sender,
+    };
+    OWNERSHIP.save(
+        deps.storage,
+        &Ownership {
+            owner: Some(owner),
+            pending_owner: None,
+        },
+    )?;
     if let Some(marketing) = msg.marketing {
         let logo = if let Some(logo) = marketing.logo {
             verify_logo(&logo)?;
@@ -168,14 +209,13 @@ pub fn instantiate(
 pub fn create_accounts(
     deps: &mut DepsMut,
     accounts: &[Cw20Coin],
-    channel: String,
 ) -> Result<Uint128, ContractError> {
     validate_accounts(accounts)?;
 
     let mut total_supply = Uint128::zero();
     for row in accounts {
         let address = deps.api.addr_validate(&row.address)?;
-        BALANCES.save(deps.storage, (channel.clone(), &address), &row.amount)?;
+        BALANCES.save(deps.storage, &address, &row.amount)?;
         total_supply += row.amount;
     }
 
@@ -200,58 +240,220 @@ pub fn execute(
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
-    channel: String,
 ) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::SetGuardian { .. }
+        | ExecuteMsg::Pause { .. }
+        | ExecuteMsg::Unpause { .. }
+        | ExecuteMsg::ProposeOwner { .. }
+        | ExecuteMsg::AcceptOwner {}
+        | ExecuteMsg::RenounceOwnership {} => {}
+        _ => ensure_not_paused(deps.storage)?,
+    }
+
     match msg {
         ExecuteMsg::Transfer { recipient, amount } => {
-            execute_transfer(deps, env, info, recipient, amount, channel)
+            execute_transfer(deps, env, info, recipient, amount)
         }
-        ExecuteMsg::Burn { amount } => execute_burn(deps, env, info, amount, channel),
+        ExecuteMsg::Burn { amount } => execute_burn(deps, env, info, amount),
         ExecuteMsg::Send {
             contract,
             amount,
             msg,
-        } => execute_send(deps, env, info, contract, amount, msg, channel),
-        ExecuteMsg::Mint { recipient, amount } => execute_mint(deps, env, info, recipient, amount, channel),
+        } => execute_send(deps, env, info, contract, amount, msg),
+        ExecuteMsg::Mint { recipient, amount } => execute_mint(deps, env, info, recipient, amount),
         ExecuteMsg::IncreaseAllowance {
             spender,
             amount,
             expires,
-        } => execute_increase_allowance(deps, env, info, spender, amount, expires, channel),
+        } => execute_increase_allowance(deps, env, info, spender, amount, expires),
         ExecuteMsg::DecreaseAllowance {
             spender,
             amount,
             expires,
-        } => execute_decrease_allowance(deps, env, info, spender, amount, expires, channel),
+        } => execute_decrease_allowance(deps, env, info, spender, amount, expires),
         ExecuteMsg::TransferFrom {
             owner,
             recipient,
             amount,
-        } => execute_transfer_from(deps, env, info, owner, recipient, amount, channel),
-        ExecuteMsg::BurnFrom { owner, amount } => execute_burn_from(deps, env, info, owner, amount, channel),
+        } => execute_transfer_from(deps, env, info, owner, recipient, amount),
+        ExecuteMsg::BurnFrom { owner, amount } => execute_burn_from(deps, env, info, owner, amount),
         ExecuteMsg::SendFrom {
             owner,
             contract,
             amount,
             msg,
-        } => execute_send_from(deps, env, info, owner, contract, amount, msg, channel),
+        } => execute_send_from(deps, env, info, owner, contract, amount, msg),
         ExecuteMsg::UpdateMarketing {
             project,
             description,
             marketing,
         } => execute_update_marketing(deps, env, info, project, description, marketing),
         ExecuteMsg::UploadLogo(logo) => execute_upload_logo(deps, env, info, logo),
-        ExecuteMsg::UpdateMinter { new_minter } => {
-            execute_update_minter(deps, env, info, new_minter, channel)
+        ExecuteMsg::ProposeMinter { new_minter, expiry } => {
+            execute_propose_minter(deps, env, info, new_minter, expiry)
+        }
+        ExecuteMsg::AcceptMinter {} => execute_accept_minter(deps, env, info),
+        ExecuteMsg::CancelMinterProposal {} => execute_cancel_minter_proposal(deps, info),
+        ExecuteMsg::RenounceMinter {} => execute_renounce_minter(deps, info),
+        ExecuteMsg::TransferRemote {
+            channel,
+            recipient,
+            amount,
+            timeout,
+            memo,
+            callback,
+            ack_callback,
+        } => execute_transfer_remote(
+            deps,
+            env,
+            info,
+            channel,
+            recipient,
+            amount,
+            timeout,
+            memo,
+            callback,
+            ack_callback,
+        ),
+        ExecuteMsg::AddChain { chain } => execute_add_chain(deps, info, chain),
+        ExecuteMsg::RemoveChain { chain } => execute_remove_chain(deps, info, chain),
+        ExecuteMsg::CloseChannel { channel } => execute_close_channel(deps, info, channel),
+        ExecuteMsg::Redeem {
+            channel,
+            remote_sender,
+            recipient,
+        } => execute_redeem(deps, info, channel, remote_sender, recipient),
+        ExecuteMsg::SetRemoteDenom { channel, denom } => {
+            execute_set_remote_denom(deps, info, channel, denom)
         }
-        //ExecuteCountMsg::Increment { channel } => Ok(Response::new()
-        //    .add_attribute("method", "execute_increment")
-        //    .add_attribute("channel", channel.clone())
-        //    .add_message(IbcMsg::SendPacket {
-        //        channel_id: channel,
-        //        data: to_binary(&IbcExecuteMsg::Increment {})?,
-        //        timeout: IbcTimeout::with_timestamp(env.block.time.plus_seconds(300)),
-        //    })),
+        ExecuteMsg::SetMainDeployment { main_deployment } => {
+            execute_set_main_deployment(deps, info, main_deployment)
+        }
+        ExecuteMsg::IncreaseRemoteAllowance {
+            channel,
+            spender,
+            amount,
+            expires,
+            timeout,
+        } => execute_remote(
+            deps,
+            env,
+            info,
+            channel,
+            IbcExecuteMsg::IncreaseAllowance {
+                spender,
+                amount,
+                expires,
+            },
+            timeout,
+        ),
+        ExecuteMsg::DecreaseRemoteAllowance {
+            channel,
+            spender,
+            amount,
+            expires,
+            timeout,
+        } => execute_remote(
+            deps,
+            env,
+            info,
+            channel,
+            IbcExecuteMsg::DecreaseAllowance {
+                spender,
+                amount,
+                expires,
+            },
+            timeout,
+        ),
+        ExecuteMsg::TransferFromRemote {
+            channel,
+            owner,
+            recipient,
+            amount,
+            timeout,
+        } => execute_remote(
+            deps,
+            env,
+            info,
+            channel,
+            IbcExecuteMsg::TransferFrom {
+                owner,
+                recipient,
+                amount,
+            },
+            timeout,
+        ),
+        ExecuteMsg::SendFromRemote {
+            channel,
+            owner,
+            contract,
+            amount,
+            msg,
+            timeout,
+        } => execute_remote(
+            deps,
+            env,
+            info,
+            channel,
+            IbcExecuteMsg::SendFrom {
+                owner,
+                contract,
+                amount,
+                msg,
+            },
+            timeout,
+        ),
+        ExecuteMsg::BurnFromRemote {
+            channel,
+            owner,
+            amount,
+            timeout,
+        } => execute_remote(
+            deps,
+            env,
+            info,
+            channel,
+            IbcExecuteMsg::BurnFrom { owner, amount },
+            timeout,
+        ),
+        ExecuteMsg::ProposeRemoteMinter {
+            channel,
+            new_minter,
+            expiry,
+            timeout,
+        } => execute_remote(
+            deps,
+            env,
+            info,
+            channel,
+            IbcExecuteMsg::ProposeMinter { new_minter, expiry },
+            timeout,
+        ),
+        ExecuteMsg::UpdateRemoteCap {
+            channel,
+            cap,
+            timeout,
+        } => execute_remote(
+            deps,
+            env,
+            info,
+            channel,
+            IbcExecuteMsg::UpdateCap { cap },
+            timeout,
+        ),
+        ExecuteMsg::SetController { controller } => execute_set_controller(deps, info, controller),
+        ExecuteMsg::SetRateLimits {
+            channel,
+            inflow,
+            outflow,
+        } => execute_set_rate_limits(deps, info, channel, inflow, outflow),
+        ExecuteMsg::SetGuardian { guardian } => execute_set_guardian(deps, info, guardian),
+        ExecuteMsg::Pause { scope } => execute_pause(deps, info, scope),
+        ExecuteMsg::Unpause { scope } => execute_unpause(deps, info, scope),
+        ExecuteMsg::ProposeOwner { new_owner } => execute_propose_owner(deps, info, new_owner),
+        ExecuteMsg::AcceptOwner {} => execute_accept_owner(deps, info),
+        ExecuteMsg::RenounceOwnership {} => execute_renounce_ownership(deps, info),
     }
 }
 
@@ -261,7 +463,6 @@ pub fn execute_transfer(
     info: MessageInfo,
     recipient: String,
     amount: Uint128,
-    channel: String,
 ) -> Result<Response, ContractError> {
     if amount == Uint128::zero() {
         return Err(ContractError::InvalidZeroAmount {});
@@ -271,14 +472,14 @@ pub fn execute_transfer(
 
     BALANCES.update(
         deps.storage,
-        (channel.clone(), &info.sender),
+        &info.sender,
         |balance: Option<Uint128>| -> StdResult<_> {
             Ok(balance.unwrap_or_default().checked_sub(amount)?)
         },
     )?;
     BALANCES.update(
         deps.storage,
-        (channel.clone(), &rcpt_addr),
+        &rcpt_addr,
         |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
     )?;
 
@@ -295,27 +496,27 @@ pub fn execute_burn(
     _env: Env,
     info: MessageInfo,
     amount: Uint128,
-    channel: String,
 ) -> Result<Response, ContractError> {
     if amount == Uint128::zero() {
         return Err(ContractError::InvalidZeroAmount {});
     }
+    ensure_burnable(deps.storage)?;
 
     // lower balance
     BALANCES.update(
         deps.storage,
-        (channel.clone(), &info.sender),
+        &info.sender,
         |balance: Option<Uint128>| -> StdResult<_> {
             Ok(balance.unwrap_or_default().checked_sub(amount)?)
         },
     )?;
     // reduce total_supply
     let mut token_info: TokenInfo = TOKEN_INFO
-        .may_load(deps.storage, channel.clone())?
+        .may_load(deps.storage)?
         .ok_or(ContractError::Unauthorized {})?;
 
-    token_info.total_supply = token_info.total_supply - amount;
-    TOKEN_INFO.save(deps.storage, channel.clone(), &token_info)?;
+    token_info.total_supply -= amount;
+    TOKEN_INFO.save(deps.storage, &token_info)?;
 
     let res = Response::new()
         .add_attribute("action", "burn")
@@ -324,20 +525,34 @@ pub fn execute_burn(
     Ok(res)
 }
 
+/// Fails on satellites. Their vouchers stand for tokens escrowed by the main
+/// deployment, burning them would lock those tokens in escrow for good.
+pub fn ensure_burnable(storage: &dyn Storage) -> Result<(), ContractError> {
+    if !IS_MAIN.load(storage)? {
+        return Err(ContractError::VoucherBurn {});
+    }
+    Ok(())
+}
+
 pub fn execute_mint(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
     recipient: String,
     amount: Uint128,
-    channel: String,
 ) -> Result<Response, ContractError> {
     if amount == Uint128::zero() {
         return Err(ContractError::InvalidZeroAmount {});
     }
+    if !IS_MAIN.load(deps.storage)? {
+        return Err(ContractError::NotMainDeployment {});
+    }
+    // validated before the supply changes, `Mint` packets must not leave
+    // anything behind when they fail
+    let rcpt_addr = deps.api.addr_validate(&recipient)?;
 
     let mut config = TOKEN_INFO
-        .may_load(deps.storage, channel.clone())?
+        .may_load(deps.storage)?
         .ok_or(ContractError::Unauthorized {})?;
 
     if config
@@ -357,13 +572,12 @@ pub fn execute_mint(
             return Err(ContractError::CannotExceedCap {});
         }
     }
-    TOKEN_INFO.save(deps.storage, channel.clone(), &config)?;
+    TOKEN_INFO.save(deps.storage, &config)?;
 
     // add amount to recipient balance
-    let rcpt_addr = deps.api.addr_validate(&recipient)?;
     BALANCES.update(
         deps.storage,
-        (channel.clone(), &rcpt_addr),
+        &rcpt_addr,
         |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
     )?;
 
@@ -381,7 +595,6 @@ pub fn execute_send(
     contract: String,
     amount: Uint128,
     msg: Binary,
-    channel: String,
 ) -> Result<Response, ContractError> {
     if amount == Uint128::zero() {
         return Err(ContractError::InvalidZeroAmount {});
@@ -392,14 +605,14 @@ pub fn execute_send(
     // move the tokens to the contract
     BALANCES.update(
         deps.storage,
-        (channel.clone(), &info.sender),
+        &info.sender,
         |balance: Option<Uint128>| -> StdResult<_> {
             Ok(balance.unwrap_or_default().checked_sub(amount)?)
         },
     )?;
     BALANCES.update(
         deps.storage,
-        (channel.clone(), &rcpt_addr),
+        &rcpt_addr,
         |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
     )?;
 
@@ -419,43 +632,681 @@ pub fn execute_send(
     Ok(res)
 }
 
-pub fn execute_update_minter(
+/// Removes tokens leaving over `channel` from the local supply. The main
+/// deployment escrows them, satellites burn the vouchers.
+pub fn escrow_or_burn(
+    storage: &mut dyn Storage,
+    channel: String,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    if IS_MAIN.load(storage)? {
+        CHANNEL_ESCROW.update(storage, channel, |escrow| -> StdResult<_> {
+            Ok(escrow.unwrap_or_default() + amount)
+        })?;
+    } else {
+        // a satellite can't send back more than it received over the channel
+        VOUCHER_SUPPLY.update(storage, channel, |supply| -> StdResult<_> {
+            Ok(supply.unwrap_or_default().checked_sub(amount)?)
+        })?;
+        TOKEN_INFO.update(storage, |mut info| -> StdResult<_> {
+            info.total_supply = info.total_supply.checked_sub(amount)?;
+            Ok(info)
+        })?;
+    }
+    Ok(())
+}
+
+/// Adds tokens arriving over `channel`, or refunded to a sender, to the local
+/// supply. The main deployment releases them from escrow, satellites mint
+/// vouchers. As the main deployment can't release more than it escrowed,
+/// a satellite can't bring more tokens back than were sent to it. Satellites
+/// only accept tokens arriving from the main deployment, see
+/// `ensure_from_main_deployment`.
+pub fn release_or_mint(
+    storage: &mut dyn Storage,
+    channel: String,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    if IS_MAIN.load(storage)? {
+        CHANNEL_ESCROW.update(storage, channel, |escrow| -> StdResult<_> {
+            Ok(escrow.unwrap_or_default().checked_sub(amount)?)
+        })?;
+    } else {
+        VOUCHER_SUPPLY.update(storage, channel, |supply| -> StdResult<_> {
+            Ok(supply.unwrap_or_default() + amount)
+        })?;
+        TOKEN_INFO.update(storage, |mut info| -> StdResult<_> {
+            info.total_supply += amount;
+            Ok(info)
+        })?;
+    }
+    Ok(())
+}
+
+/// Fails unless a satellite can mint vouchers for tokens arriving over
+/// `channel`, which requires its counterparty to be the main deployment.
+/// Anything else could mint vouchers out of nothing. The main deployment
+/// only releases what it escrowed, so it accepts tokens from any channel.
+pub fn ensure_from_main_deployment(
+    storage: &dyn Storage,
+    channel: &str,
+) -> Result<(), ContractError> {
+    if IS_MAIN.load(storage)? {
+        return Ok(());
+    }
+    let main = MAIN_DEPLOYMENT.may_load(storage)?;
+    let info = CHANNELS.may_load(storage, channel.to_string())?;
+    match (main, info) {
+        (Some(main), Some(info))
+            if info.connection_id == main.connection_id
+                && info.counterparty_port_id == main.port_id =>
+        {
+            Ok(())
+        }
+        _ => Err(ContractError::NotMainChannel {
+            channel: channel.to_string(),
+        }),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_transfer_remote(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
-    new_minter: Option<String>,
     channel: String,
+    recipient: String,
+    amount: Uint128,
+    timeout: Option<u64>,
+    memo: Option<String>,
+    callback: Option<Callback>,
+    ack_callback: Option<Callback>,
 ) -> Result<Response, ContractError> {
-    let mut config = TOKEN_INFO
-        .may_load(deps.storage, channel.clone())?
-        .ok_or(ContractError::Unauthorized {})?;
+    if amount == Uint128::zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    ensure_channel_open(deps.storage, &channel)?;
+    ensure_send_not_paused(deps.storage, &channel)?;
+    if is_control_channel(deps.storage, &channel)? {
+        return Err(ContractError::WrongChannelType { channel });
+    }
+    let ics20 = is_ics20_channel(deps.storage, &channel)?;
+    if ics20 && (callback.is_some() || ack_callback.is_some()) {
+        return Err(ContractError::CallbackNotSupported { channel });
+    }
+
+    // take the tokens out of circulation while they are in flight, they are
+    // credited to the recipient by the counterparty when it receives the packet
+    BALANCES.update(
+        deps.storage,
+        &info.sender,
+        |balance: Option<Uint128>| -> StdResult<_> {
+            Ok(balance.unwrap_or_default().checked_sub(amount)?)
+        },
+    )?;
+    escrow_or_burn(deps.storage, channel.clone(), amount)?;
+    // counted as held by the counterparty right away, it can send them back
+    // before we see the ack
+    CHANNEL_OUTSTANDING.update(
+        deps.storage,
+        channel.clone(),
+        |outstanding: Option<Uint128>| -> StdResult<_> {
+            Ok(outstanding.unwrap_or_default() + amount)
+        },
+    )?;
+    rate_limit::consume(deps.storage, &env, &channel, Flow::Out, amount)?;
+
+    let timeout = env
+        .block
+        .time
+        .plus_seconds(timeout.unwrap_or(DEFAULT_TIMEOUT_SECONDS));
+    update_stats(deps.storage, &channel, |stats| {
+        stats.packets_sent += 1;
+        stats.volume_sent += amount;
+    })?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "transfer_remote")
+        .add_attribute("from", &info.sender)
+        .add_attribute("to", &recipient)
+        .add_attribute("channel", &channel)
+        .add_attribute("amount", amount);
+
+    let packet = if ics20 {
+        to_binary(&Ics20Packet {
+            denom: ics20_denom(deps.storage, &env, &channel)?,
+            amount,
+            sender: info.sender.to_string(),
+            receiver: recipient,
+            memo,
+        })?
+    } else {
+        let (nonce, packet) = make_packet(
+            deps.storage,
+            &env,
+            &info.sender,
+            IbcExecuteMsg::Transfer {
+                receipient: recipient,
+                amount,
+                memo,
+                callback,
+            },
+        )?;
+        IN_FLIGHT.save(
+            deps.storage,
+            nonce,
+            &InFlightPacket {
+                sender: info.sender,
+                amount,
+                channel: channel.clone(),
+                callback: ack_callback,
+                sent_at: Some(env.block.time),
+            },
+        )?;
+        res = res.add_attribute("nonce", nonce.to_string());
+        packet
+    };
+
+    Ok(res.add_message(IbcMsg::SendPacket {
+        channel_id: channel,
+        data: packet,
+        timeout: IbcTimeout::with_timestamp(timeout),
+    }))
+}
+
+/// Sends `msg` to be executed on the counterparty of `channel` by the account
+/// acting for the sender there, or by the counterparty itself for control
+/// messages. Nothing changes locally until it is acknowledged.
+pub fn execute_remote(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    channel: String,
+    msg: IbcExecuteMsg,
+    timeout: Option<u64>,
+) -> Result<Response, ContractError> {
+    ensure_channel_open(deps.storage, &channel)?;
+    ensure_send_not_paused(deps.storage, &channel)?;
+    if is_ics20_channel(deps.storage, &channel)? {
+        return Err(ContractError::Ics20Channel { channel });
+    }
+    // The counterparty executes control messages on its own authority, only
+    // the owner may send them.
+    if msg.is_control() {
+        ensure_owner(deps.as_ref(), &info.sender)?;
+    }
+    if msg.is_control() != is_control_channel(deps.storage, &channel)? {
+        return Err(ContractError::WrongChannelType { channel });
+    }
+
+    let timeout = env
+        .block
+        .time
+        .plus_seconds(timeout.unwrap_or(DEFAULT_TIMEOUT_SECONDS));
+    let (nonce, packet) = make_packet(deps.storage, &env, &info.sender, msg)?;
+    update_stats(deps.storage, &channel, |stats| stats.packets_sent += 1)?;
+
+    let res = Response::new()
+        .add_attribute("action", "execute_remote")
+        .add_attribute("from", info.sender)
+        .add_attribute("channel", &channel)
+        .add_attribute("nonce", nonce.to_string())
+        .add_message(IbcMsg::SendPacket {
+            channel_id: channel,
+            data: packet,
+            timeout: IbcTimeout::with_timestamp(timeout),
+        });
+    Ok(res)
+}
 
-    let mint = config.mint.as_ref().ok_or(ContractError::Unauthorized {})?;
-    if mint.minter != info.sender {
+/// Fails unless `channel` completed the handshake and hasn't started closing.
+pub fn ensure_channel_open(storage: &dyn Storage, channel: &str) -> Result<(), ContractError> {
+    let info = CHANNELS
+        .may_load(storage, channel.to_string())?
+        .ok_or_else(|| ContractError::UnknownChannel {
+            channel: channel.to_string(),
+        })?;
+    if info.status != ChannelStatus::Open {
+        return Err(ContractError::ChannelNotOpen {
+            channel: channel.to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn ensure_owner(deps: Deps, sender: &Addr) -> Result<(), ContractError> {
+    if OWNERSHIP.load(deps.storage)?.owner.as_ref() != Some(sender) {
         return Err(ContractError::Unauthorized {});
     }
+    Ok(())
+}
+
+pub fn execute_propose_owner(
+    deps: DepsMut,
+    info: MessageInfo,
+    new_owner: String,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
+    let new_owner = deps.api.addr_validate(&new_owner)?;
+    let mut ownership = OWNERSHIP.load(deps.storage)?;
+    ownership.pending_owner = Some(new_owner.clone());
+    OWNERSHIP.save(deps.storage, &ownership)?;
+
+    let res = Response::new()
+        .add_attribute("action", "propose_owner")
+        .add_attribute("pending_owner", new_owner);
+    Ok(res)
+}
+
+pub fn execute_accept_owner(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut ownership = OWNERSHIP.load(deps.storage)?;
+    match ownership.pending_owner {
+        None => return Err(ContractError::NoPendingOwner {}),
+        Some(ref pending) if *pending != info.sender => return Err(ContractError::Unauthorized {}),
+        Some(_) => {}
+    }
+    ownership.owner = ownership.pending_owner.take();
+    OWNERSHIP.save(deps.storage, &ownership)?;
+
+    let res = Response::new()
+        .add_attribute("action", "accept_owner")
+        .add_attribute("owner", info.sender);
+    Ok(res)
+}
+
+pub fn execute_renounce_ownership(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
+    // only the owner can unpause, nothing paused now could ever resume
+    if is_anything_paused(deps.storage)? {
+        return Err(ContractError::RenounceWhilePaused {});
+    }
+    OWNERSHIP.save(
+        deps.storage,
+        &Ownership {
+            owner: None,
+            pending_owner: None,
+        },
+    )?;
+    // the guardian pauses on behalf of the owner, with no owner left to
+    // unpause it could stop the contract for good
+    GUARDIAN.remove(deps.storage);
+
+    let res = Response::new().add_attribute("action", "renounce_ownership");
+    Ok(res)
+}
+
+pub fn execute_add_chain(
+    deps: DepsMut,
+    info: MessageInfo,
+    chain: String,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
+
+    let mut chains = CHAINS.load(deps.storage)?;
+    if chains.is_allowed(chain.clone())? {
+        return Err(ContractError::ChainAlreadyAllowed { chain });
+    }
+    chains.other_chains.push(chain.clone());
+    CHAINS.save(deps.storage, &chains)?;
+
+    let res = Response::new()
+        .add_attribute("action", "add_chain")
+        .add_attribute("chain", chain);
+    Ok(res)
+}
+
+pub fn execute_remove_chain(
+    deps: DepsMut,
+    info: MessageInfo,
+    chain: String,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
 
-    let minter_data = new_minter
-        .map(|new_minter| deps.api.addr_validate(&new_minter))
-        .transpose()?
-        .map(|minter| MinterData {
-            minter,
-            cap: mint.cap,
+    let mut chains = CHAINS.load(deps.storage)?;
+    if !chains.is_allowed(chain.clone())? {
+        return Err(ContractError::ChainNotAllowed { chain });
+    }
+    chains.other_chains.retain(|c| *c != chain);
+    CHAINS.save(deps.storage, &chains)?;
+
+    let res = Response::new()
+        .add_attribute("action", "remove_chain")
+        .add_attribute("chain", chain);
+    Ok(res)
+}
+
+pub fn execute_close_channel(
+    deps: DepsMut,
+    info: MessageInfo,
+    channel: String,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
+
+    let mut channel_info = CHANNELS
+        .may_load(deps.storage, channel.clone())?
+        .ok_or_else(|| ContractError::UnknownChannel {
+            channel: channel.clone(),
+        })?;
+    if channel_info.status != ChannelStatus::Open {
+        return Err(ContractError::ChannelNotOpen { channel });
+    }
+    channel_info.status = ChannelStatus::Closing;
+    CHANNELS.save(deps.storage, channel.clone(), &channel_info)?;
+
+    let res = Response::new()
+        .add_attribute("action", "close_channel")
+        .add_attribute("channel", &channel)
+        .add_message(IbcMsg::CloseChannel {
+            channel_id: channel,
         });
+    Ok(res)
+}
+
+/// Recovers the balance stranded in a remote account of a closed channel. The
+/// owner acts as custodian for the remote sender, nothing checks that
+/// `recipient` belongs to them.
+pub fn execute_redeem(
+    deps: DepsMut,
+    info: MessageInfo,
+    channel: String,
+    remote_sender: String,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
+
+    let channel_info = CHANNELS
+        .may_load(deps.storage, channel.clone())?
+        .ok_or_else(|| ContractError::UnknownChannel {
+            channel: channel.clone(),
+        })?;
+    if channel_info.status != ChannelStatus::Closed {
+        return Err(ContractError::ChannelNotClosed { channel });
+    }
+
+    let account = remote_account(deps.api, &channel, &remote_sender)?;
+    let amount = BALANCES
+        .may_load(deps.storage, &account)?
+        .unwrap_or_default();
+    if amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let rcpt_addr = deps.api.addr_validate(&recipient)?;
+    BALANCES.remove(deps.storage, &account);
+    BALANCES.update(
+        deps.storage,
+        &rcpt_addr,
+        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
+    )?;
+
+    let res = Response::new()
+        .add_attribute("action", "redeem")
+        .add_attribute("channel", channel)
+        .add_attribute("remote_sender", remote_sender)
+        .add_attribute("from", account)
+        .add_attribute("to", recipient)
+        .add_attribute("amount", amount);
+    Ok(res)
+}
+
+pub fn execute_set_remote_denom(
+    deps: DepsMut,
+    info: MessageInfo,
+    channel: String,
+    denom: String,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
+    // tokens of a remote denom are vouchers, the main deployment has no
+    // escrow to release them from
+    if IS_MAIN.load(deps.storage)? {
+        return Err(ContractError::RemoteDenomOnMain {});
+    }
+    if !is_ics20_channel(deps.storage, &channel)? {
+        return Err(ContractError::NotIcs20Channel { channel });
+    }
+    REMOTE_DENOMS.save(deps.storage, channel.clone(), &denom)?;
+
+    let res = Response::new()
+        .add_attribute("action", "set_remote_denom")
+        .add_attribute("channel", channel)
+        .add_attribute("denom", denom);
+    Ok(res)
+}
+
+pub fn execute_set_main_deployment(
+    deps: DepsMut,
+    info: MessageInfo,
+    main_deployment: MainDeployment,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
+    if IS_MAIN.load(deps.storage)? {
+        return Err(ContractError::NotSatellite {});
+    }
+    MAIN_DEPLOYMENT.save(deps.storage, &main_deployment)?;
+
+    let res = Response::new()
+        .add_attribute("action", "set_main_deployment")
+        .add_attribute("connection_id", main_deployment.connection_id)
+        .add_attribute("port_id", main_deployment.port_id);
+    Ok(res)
+}
+
+pub fn execute_set_controller(
+    deps: DepsMut,
+    info: MessageInfo,
+    controller: Option<Controller>,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
+    // control packets govern minting, which only the main deployment does
+    if !IS_MAIN.load(deps.storage)? {
+        return Err(ContractError::NotMainDeployment {});
+    }
+    let mut res = Response::new().add_attribute("action", "set_controller");
+    match controller {
+        Some(controller) => {
+            if !is_control_channel(deps.storage, &controller.channel)? {
+                return Err(ContractError::WrongChannelType {
+                    channel: controller.channel,
+                });
+            }
+            CONTROLLER.save(deps.storage, &controller)?;
+            res = res
+                .add_attribute("channel", controller.channel)
+                .add_attribute("sender", controller.sender);
+        }
+        None => CONTROLLER.remove(deps.storage),
+    }
+    Ok(res)
+}
+
+pub fn execute_set_rate_limits(
+    deps: DepsMut,
+    info: MessageInfo,
+    channel: String,
+    inflow: Option<RateLimit>,
+    outflow: Option<RateLimit>,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
+    if !CHANNELS.has(deps.storage, channel.clone()) {
+        return Err(ContractError::UnknownChannel { channel });
+    }
+    if [&inflow, &outflow]
+        .iter()
+        .any(|limit| matches!(limit, Some(limit) if limit.window_seconds == 0))
+    {
+        return Err(ContractError::InvalidRateLimit {});
+    }
+    RATE_LIMITS.save(
+        deps.storage,
+        channel.clone(),
+        &RateLimits { inflow, outflow },
+    )?;
+
+    let res = Response::new()
+        .add_attribute("action", "set_rate_limits")
+        .add_attribute("channel", channel);
+    Ok(res)
+}
+
+pub fn execute_set_guardian(
+    deps: DepsMut,
+    info: MessageInfo,
+    guardian: Option<String>,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
+    let mut res = Response::new().add_attribute("action", "set_guardian");
+    match guardian {
+        Some(guardian) => {
+            let guardian = deps.api.addr_validate(&guardian)?;
+            GUARDIAN.save(deps.storage, &guardian)?;
+            res = res.add_attribute("guardian", guardian);
+        }
+        None => GUARDIAN.remove(deps.storage),
+    }
+    Ok(res)
+}
+
+pub fn execute_pause(
+    deps: DepsMut,
+    info: MessageInfo,
+    scope: PauseScope,
+) -> Result<Response, ContractError> {
+    if !is_guardian(deps.storage, &info.sender)? {
+        ensure_owner(deps.as_ref(), &info.sender)?;
+    }
+    if let PauseScope::Channel { channel } = &scope {
+        if !CHANNELS.has(deps.storage, channel.clone()) {
+            return Err(ContractError::UnknownChannel {
+                channel: channel.clone(),
+            });
+        }
+    }
+    set_paused(deps.storage, &scope, true)?;
 
-    config.mint = minter_data;
+    let res = Response::new()
+        .add_attribute("action", "pause")
+        .add_attribute("scope", scope_name(&scope));
+    Ok(res)
+}
 
-    TOKEN_INFO.save(deps.storage, channel.clone(), &config)?;
+pub fn execute_unpause(
+    deps: DepsMut,
+    info: MessageInfo,
+    scope: PauseScope,
+) -> Result<Response, ContractError> {
+    ensure_owner(deps.as_ref(), &info.sender)?;
+    set_paused(deps.storage, &scope, false)?;
+
+    let res = Response::new()
+        .add_attribute("action", "unpause")
+        .add_attribute("scope", scope_name(&scope));
+    Ok(res)
+}
+
+fn scope_name(scope: &PauseScope) -> String {
+    match scope {
+        PauseScope::All => "all".to_string(),
+        PauseScope::IbcReceive => "ibc_receive".to_string(),
+        PauseScope::IbcSend => "ibc_send".to_string(),
+        PauseScope::Channel { channel } => format!("channel:{}", channel),
+    }
+}
+
+fn ensure_minter(deps: Deps, sender: &Addr) -> Result<(), ContractError> {
+    let config = TOKEN_INFO
+        .may_load(deps.storage)?
+        .ok_or(ContractError::Unauthorized {})?;
+    match config.mint {
+        Some(mint) if mint.minter == *sender => Ok(()),
+        _ => Err(ContractError::Unauthorized {}),
+    }
+}
+
+pub fn execute_propose_minter(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_minter: String,
+    expiry: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    ensure_minter(deps.as_ref(), &info.sender)?;
+    let expiry = expiry.unwrap_or_default();
+    if expiry.is_expired(&env.block) {
+        return Err(ContractError::MinterProposalExpired {});
+    }
+    let minter = deps.api.addr_validate(&new_minter)?;
+    PENDING_MINTER.save(
+        deps.storage,
+        &PendingMinter {
+            minter: minter.clone(),
+            expiry,
+        },
+    )?;
+
+    Ok(Response::default()
+        .add_attribute("action", "propose_minter")
+        .add_attribute("pending_minter", minter)
+        .add_attribute("expiry", expiry.to_string()))
+}
+
+pub fn execute_accept_minter(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let pending = PENDING_MINTER
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingMinter {})?;
+    if pending.minter != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if pending.expiry.is_expired(&env.block) {
+        return Err(ContractError::MinterProposalExpired {});
+    }
+
+    // the proposal was made by the minter, if it has since been removed
+    // there is nothing to hand over
+    let mut config = TOKEN_INFO.load(deps.storage)?;
+    let mint = config
+        .mint
+        .as_mut()
+        .ok_or(ContractError::NoPendingMinter {})?;
+    mint.minter = pending.minter;
+    TOKEN_INFO.save(deps.storage, &config)?;
+    PENDING_MINTER.remove(deps.storage);
 
     Ok(Response::default()
-        .add_attribute("action", "update_minter")
-        .add_attribute(
-            "new_minter",
-            config
-                .mint
-                .map(|m| m.minter.into_string())
-                .unwrap_or_else(|| "None".to_string()),
-        ))
+        .add_attribute("action", "accept_minter")
+        .add_attribute("new_minter", info.sender))
+}
+
+pub fn execute_cancel_minter_proposal(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    ensure_minter(deps.as_ref(), &info.sender)?;
+    if PENDING_MINTER.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::NoPendingMinter {});
+    }
+    PENDING_MINTER.remove(deps.storage);
+
+    Ok(Response::default().add_attribute("action", "cancel_minter_proposal"))
+}
+
+pub fn execute_renounce_minter(
+    deps: DepsMut,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    ensure_minter(deps.as_ref(), &info.sender)?;
+    let mut config = TOKEN_INFO.load(deps.storage)?;
+    config.mint = None;
+    TOKEN_INFO.save(deps.storage, &config)?;
+    PENDING_MINTER.remove(deps.storage);
+
+    Ok(Response::default().add_attribute("action", "renounce_minter"))
 }
 
 pub fn execute_update_marketing(
@@ -547,33 +1398,48 @@ pub fn execute_upload_logo(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::GetCount { channel } => to_binary(&query_count(deps, channel)?),
-        QueryMsg::Balance { channel, address } => {
-            to_binary(&query_balance(deps, address, channel)?)
+        QueryMsg::SupplyBreakdown {} => to_binary(&query_supply_breakdown(deps)?),
+        QueryMsg::ListChannels { start_after, limit } => {
+            to_binary(&query_list_channels(deps, start_after, limit)?)
         }
-        QueryMsg::TokenInfo { channel } => to_binary(&query_token_info(deps, channel)?),
-        QueryMsg::Minter { channel } => to_binary(&query_minter(deps, channel)?),
-        QueryMsg::Allowance {
+        QueryMsg::Channel { id } => to_binary(&CHANNELS.load(deps.storage, id)?),
+        QueryMsg::RemoteAllowance {
             channel,
             owner,
             spender,
-        } => to_binary(&query_allowance(deps, owner, spender, channel)?),
+        } => to_binary(
+            &REMOTE_ALLOWANCES
+                .may_load(deps.storage, (channel, owner, spender))?
+                .unwrap_or_default(),
+        ),
+        QueryMsg::MainDeployment {} => to_binary(&MAIN_DEPLOYMENT.may_load(deps.storage)?),
+        QueryMsg::Controller {} => to_binary(&CONTROLLER.may_load(deps.storage)?),
+        QueryMsg::Ownership {} => to_binary(&OWNERSHIP.load(deps.storage)?),
+        QueryMsg::PauseStatus {} => to_binary(&query_pause_status(deps.storage)?),
+        QueryMsg::RateLimitUsage { channel } => {
+            to_binary(&query_rate_limit_usage(deps.storage, env, channel)?)
+        }
+        QueryMsg::DenomTrace { channel } => to_binary(&query_denom_trace(deps, env, channel)?),
+        QueryMsg::ChannelStats { channel } => to_binary(
+            &CHANNEL_STATS
+                .may_load(deps.storage, channel)?
+                .unwrap_or_default(),
+        ),
+        QueryMsg::Balance { address } => to_binary(&query_balance(deps, address)?),
+        QueryMsg::TokenInfo {} => to_binary(&query_token_info(deps)?),
+        QueryMsg::Minter {} => to_binary(&query_minter(deps)?),
+        QueryMsg::PendingMinter {} => to_binary(&query_pending_minter(deps)?),
+        QueryMsg::Allowance { owner, spender } => {
+            to_binary(&query_allowance(deps, owner, spender)?)
+        }
         QueryMsg::AllAllowances {
-            channel,
             owner,
             start_after,
             limit,
-        } => to_binary(&query_owner_allowances(
-            deps,
-            owner,
-            start_after,
-            limit,
-            channel,
-        )?),
+        } => to_binary(&query_owner_allowances(deps, owner, start_after, limit)?),
         QueryMsg::AllSpenderAllowances {
-            channel,
             spender,
             start_after,
             limit,
@@ -582,26 +1448,30 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             spender,
             start_after,
             limit,
-            channel,
         )?),
-        //QueryMsg::AllAccounts { start_after, limit } => {
-        //    to_binary(&query_all_accounts(deps, start_after, limit)?)
-        //}
+        QueryMsg::AllAccounts { start_after, limit } => {
+            to_binary(&query_all_accounts(deps, start_after, limit)?)
+        }
+        QueryMsg::AllBalances {
+            channel,
+            start_after,
+            limit,
+        } => to_binary(&query_all_balances(deps, channel, start_after, limit)?),
         QueryMsg::MarketingInfo {} => to_binary(&query_marketing_info(deps)?),
         QueryMsg::DownloadLogo {} => to_binary(&query_download_logo(deps)?),
     }
 }
 
-pub fn query_balance(deps: Deps, address: String, channel: String) -> StdResult<BalanceResponse> {
+pub fn query_balance(deps: Deps, address: String) -> StdResult<BalanceResponse> {
     let address = deps.api.addr_validate(&address)?;
     let balance = BALANCES
-        .may_load(deps.storage, (channel, &address))?
+        .may_load(deps.storage, &address)?
         .unwrap_or_default();
     Ok(BalanceResponse { balance })
 }
 
-pub fn query_token_info(deps: Deps, channel: String) -> StdResult<TokenInfoResponse> {
-    let info = TOKEN_INFO.load(deps.storage, channel)?;
+pub fn query_token_info(deps: Deps) -> StdResult<TokenInfoResponse> {
+    let info = TOKEN_INFO.load(deps.storage)?;
     let res = TokenInfoResponse {
         name: info.name,
         symbol: info.symbol,
@@ -611,8 +1481,8 @@ pub fn query_token_info(deps: Deps, channel: String) -> StdResult<TokenInfoRespo
     Ok(res)
 }
 
-pub fn query_minter(deps: Deps, channel: String) -> StdResult<Option<MinterResponse>> {
-    let meta = TOKEN_INFO.load(deps.storage, channel)?;
+pub fn query_minter(deps: Deps) -> StdResult<Option<MinterResponse>> {
+    let meta = TOKEN_INFO.load(deps.storage)?;
     let minter = match meta.mint {
         Some(m) => Some(MinterResponse {
             minter: m.minter.into(),
@@ -623,19 +1493,58 @@ pub fn query_minter(deps: Deps, channel: String) -> StdResult<Option<MinterRespo
     Ok(minter)
 }
 
-pub fn query_marketing_info(deps: Deps) -> StdResult<MarketingInfoResponse> {
-    Ok(MARKETING_INFO.may_load(deps.storage)?.unwrap_or_default())
+pub fn query_pending_minter(deps: Deps) -> StdResult<Option<PendingMinterResponse>> {
+    let pending = PENDING_MINTER
+        .may_load(deps.storage)?
+        .map(|pending| PendingMinterResponse {
+            pending_minter: pending.minter.into(),
+            expiry: pending.expiry,
+        });
+    Ok(pending)
+}
+
+fn sum_by_channel(amounts: &mut Vec<ChannelAmount>, channel: String, amount: Uint128) {
+    match amounts.iter_mut().find(|a| a.channel == channel) {
+        Some(entry) => entry.amount += amount,
+        None => amounts.push(ChannelAmount { channel, amount }),
+    }
 }
-pub fn try_increment(deps: DepsMut, channel: String) -> Result<u32, StdError> {
-    CONNECTION_COUNTS.update(deps.storage, channel, |count| -> StdResult<_> {
-        Ok(count.unwrap_or_default() + 1)
+
+fn load_channel_amounts(deps: Deps, map: &Map<String, Uint128>) -> StdResult<Vec<ChannelAmount>> {
+    map.range(deps.storage, None, None, Ascending)
+        .map(|item| item.map(|(channel, amount)| ChannelAmount { channel, amount }))
+        .collect()
+}
+
+pub fn query_supply_breakdown(deps: Deps) -> StdResult<SupplyBreakdownResponse> {
+    let escrowed = load_channel_amounts(deps, &CHANNEL_ESCROW)?;
+    let vouchers = load_channel_amounts(deps, &VOUCHER_SUPPLY)?;
+    let outstanding = load_channel_amounts(deps, &CHANNEL_OUTSTANDING)?;
+
+    let mut in_flight = vec![];
+    for item in IN_FLIGHT.range(deps.storage, None, None, Ascending) {
+        let (_, packet) = item?;
+        sum_by_channel(&mut in_flight, packet.channel, packet.amount);
+    }
+
+    // Issued tokens and minted vouchers circulate unless they sit in escrow.
+    let local_supply = TOKEN_INFO
+        .load(deps.storage)?
+        .total_supply
+        .checked_sub(escrowed.iter().map(|e| e.amount).sum())?;
+
+    Ok(SupplyBreakdownResponse {
+        is_main: IS_MAIN.load(deps.storage)?,
+        local_supply,
+        escrowed,
+        vouchers,
+        in_flight,
+        outstanding,
     })
 }
-fn query_count(deps: Deps, channel: String) -> StdResult<GetCountResponse> {
-    let count = CONNECTION_COUNTS
-        .may_load(deps.storage, channel)?
-        .unwrap_or_default();
-    Ok(GetCountResponse { count })
+
+pub fn query_marketing_info(deps: Deps) -> StdResult<MarketingInfoResponse> {
+    Ok(MARKETING_INFO.may_load(deps.storage)?.unwrap_or_default())
 }
 pub fn query_download_logo(deps: Deps) -> StdResult<DownloadLogoResponse> {
     let logo = LOGO.load(deps.storage)?;
@@ -653,12 +1562,17 @@ pub fn query_download_logo(deps: Deps) -> StdResult<DownloadLogoResponse> {
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(
-    deps: DepsMut,
-    _env: Env,
-    _msg: MigrateMsg,
-    channel: String,
-) -> Result<Response, ContractError> {
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        SEND_REPLY_ID => reply_send(deps, msg.result),
+        TRANSFER_CALLBACK_REPLY_ID => reply_transfer_callback(deps, env, msg.result),
+        ACK_CALLBACK_REPLY_ID => reply_ack_callback(msg.result),
+        id => Err(StdError::generic_err(format!("Unknown reply id {}", id)).into()),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
     let original_version =
         ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
@@ -667,8 +1581,8 @@ pub fn migrate(
         let data = ALLOWANCES
             .range(deps.storage, None, None, Ascending)
             .collect::<StdResult<Vec<_>>>()?;
-        for ((channel, owner, spender), allowance) in data {
-            ALLOWANCES_SPENDER.save(deps.storage, (channel, &spender, &owner), &allowance)?;
+        for ((owner, spender), allowance) in data {
+            ALLOWANCES_SPENDER.save(deps.storage, (&spender, &owner), &allowance)?;
         }
     }
     Ok(Response::default())
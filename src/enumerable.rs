@@ -4,7 +4,8 @@ use cw20::{
     SpenderAllowanceInfo,
 };
 
-use crate::state::{ALLOWANCES, ALLOWANCES_SPENDER, BALANCES};
+use crate::msg::{AccountBalance, AllBalancesResponse, ListChannelsResponse};
+use crate::state::{ALLOWANCES, ALLOWANCES_SPENDER, BALANCES, CHANNELS, REMOTE_ACCOUNTS};
 use cw_storage_plus::Bound;
 
 // settings for pagination
@@ -16,14 +17,13 @@ pub fn query_owner_allowances(
     owner: String,
     start_after: Option<String>,
     limit: Option<u32>,
-    channel: String,
 ) -> StdResult<AllAllowancesResponse> {
     let owner_addr = deps.api.addr_validate(&owner)?;
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let start = start_after.map(|s| Bound::ExclusiveRaw(s.into_bytes()));
 
     let allowances = ALLOWANCES
-        .prefix((channel, &owner_addr))
+        .prefix(&owner_addr)
         .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
         .map(|item| {
@@ -42,14 +42,13 @@ pub fn query_spender_allowances(
     spender: String,
     start_after: Option<String>,
     limit: Option<u32>,
-    channel: String,
 ) -> StdResult<AllSpenderAllowancesResponse> {
     let spender_addr = deps.api.addr_validate(&spender)?;
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let start = start_after.map(|s| Bound::ExclusiveRaw(s.into_bytes()));
 
     let allowances = ALLOWANCES_SPENDER
-        .prefix((channel, &spender_addr))
+        .prefix(&spender_addr)
         .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
         .map(|item| {
@@ -63,21 +62,76 @@ pub fn query_spender_allowances(
     Ok(AllSpenderAllowancesResponse { allowances })
 }
 
-//pub fn query_all_accounts(
-//    deps: Deps,
-//    start_after: Option<String>,
-//    limit: Option<u32>,
-//    channel: String,
-//) -> StdResult<AllAccountsResponse> {
-//    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-//    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into()));
-//
-//    let accounts = BALANCES
-//        .keys(deps.storage, start, None, Order::Ascending)
-//        .take(limit)
-//        .map(|item| item.map(Into::into))
-//        .collect::<StdResult<_>>()?;
-//
-//    Ok(AllAccountsResponse { accounts })
-//}
-//
+pub fn query_all_accounts(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllAccountsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into()));
+
+    let accounts = BALANCES
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(Into::into))
+        .collect::<StdResult<_>>()?;
+
+    Ok(AllAccountsResponse { accounts })
+}
+
+pub fn query_all_balances(
+    deps: Deps,
+    channel: Option<String>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllBalancesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into()));
+
+    let balances = match channel {
+        None => BALANCES
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                item.map(|(addr, balance)| AccountBalance {
+                    address: addr.into(),
+                    balance,
+                    remote_sender: None,
+                })
+            })
+            .collect::<StdResult<_>>()?,
+        Some(channel) => REMOTE_ACCOUNTS
+            .prefix(channel)
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                let (addr, remote_sender) = item?;
+                let balance = BALANCES.may_load(deps.storage, &addr)?.unwrap_or_default();
+                Ok(AccountBalance {
+                    address: addr.into(),
+                    balance,
+                    remote_sender: Some(remote_sender),
+                })
+            })
+            .collect::<StdResult<_>>()?,
+    };
+
+    Ok(AllBalancesResponse { balances })
+}
+
+pub fn query_list_channels(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ListChannelsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let channels = CHANNELS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, channel)| channel))
+        .collect::<StdResult<_>>()?;
+
+    Ok(ListChannelsResponse { channels })
+}
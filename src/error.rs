@@ -1,4 +1,7 @@
-use cosmwasm_std::StdError;
+use std::fmt;
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{to_vec, StdError};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -9,9 +12,15 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
-    #[error("only unordered channels are supported")]
+    #[error("only control channels can be ordered")]
     OrderedChannel {},
 
+    #[error("control channels must be ordered")]
+    UnorderedControlChannel {},
+
+    #[error("Packet not allowed on channel {channel}")]
+    WrongChannelType { channel: String },
+
     #[error("invalid IBC channel version. Got ({actual}), expected ({expected})")]
     InvalidVersion { actual: String, expected: String },
 
@@ -47,4 +56,221 @@ pub enum ContractError {
 
     #[error("Duplicate initial balance addresses")]
     DuplicateInitialBalanceAddresses {},
+
+    #[error("Channel {channel} is not connected")]
+    UnknownChannel { channel: String },
+
+    #[error("Channel {channel} is not open")]
+    ChannelNotOpen { channel: String },
+
+    #[error("Channel {channel} is not closed")]
+    ChannelNotClosed { channel: String },
+
+    #[error(
+        "Counterparty port ({port_id}) on connection ({connection_id}) is not an allowed chain"
+    )]
+    CounterpartyNotAllowed {
+        port_id: String,
+        connection_id: String,
+    },
+
+    #[error("Chain {chain} is already allowed")]
+    ChainAlreadyAllowed { chain: String },
+
+    #[error("Chain {chain} is not allowed")]
+    ChainNotAllowed { chain: String },
+
+    #[error("Only the main deployment can issue tokens")]
+    NotMainDeployment {},
+
+    #[error("Only satellites have a main deployment")]
+    NotSatellite {},
+
+    #[error("Channel {channel} does not lead to the main deployment")]
+    NotMainChannel { channel: String },
+
+    #[error("The main deployment issues the token, it can't originate elsewhere")]
+    RemoteDenomOnMain {},
+
+    #[error("Denom {denom} is not this token")]
+    UnknownDenom { denom: String },
+
+    #[error("Channel {channel} does not speak ICS-20")]
+    NotIcs20Channel { channel: String },
+
+    #[error("Receiving contract failed: {error}")]
+    ReceiveHookFailed { error: String },
+
+    #[error("Callback failed: {error}")]
+    CallbackFailed { error: String },
+
+    #[error("Channel {channel} does not support callbacks")]
+    CallbackNotSupported { channel: String },
+
+    #[error("Callbacks can't target the token contract")]
+    CallbackToSelf {},
+
+    #[error("Control packets from {sender} over channel {channel} are not trusted")]
+    UntrustedController { channel: String, sender: String },
+
+    #[error("Channel {channel} speaks ICS-20 and only carries transfers")]
+    Ics20Channel { channel: String },
+
+    #[error("Rate limit of channel {channel} exceeded for {flow}")]
+    RateLimitExceeded { channel: String, flow: String },
+
+    #[error("Rate limit windows must last at least a second")]
+    InvalidRateLimit {},
+
+    #[error("Contract is paused")]
+    Paused {},
+
+    #[error("Receiving IBC packets is paused")]
+    IbcReceivePaused {},
+
+    #[error("Sending IBC packets is paused")]
+    IbcSendPaused {},
+
+    #[error("Channel {channel} is paused")]
+    ChannelPaused { channel: String },
+
+    #[error("No ownership transfer is pending")]
+    NoPendingOwner {},
+
+    #[error("No minter handover is pending")]
+    NoPendingMinter {},
+
+    #[error("Minter proposal is expired")]
+    MinterProposalExpired {},
+
+    #[error("Ownership can't be renounced while anything is paused")]
+    RenounceWhilePaused {},
+
+    #[error("Vouchers can't be burned, send them back to the main deployment instead")]
+    VoucherBurn {},
+}
+
+/// Stable identifier of a `ContractError`, sent to the counterparty in error
+/// acks. Codes are never renamed or reused, new errors get new codes.
+#[cw_serde]
+#[derive(Copy, Eq)]
+pub enum ErrorCode {
+    /// The error ack came from a counterparty that doesn't send codes.
+    Unknown,
+    Std,
+    /// The packet could not be decoded.
+    InvalidPacket,
+    /// A balance, allowance or supply would go below zero or overflow.
+    Overflow,
+    OrderedChannel,
+    UnorderedControlChannel,
+    WrongChannelType,
+    InvalidVersion,
+    Unauthorized,
+    CannotSetOwnAccount,
+    InvalidZeroAmount,
+    Expired,
+    NoAllowance,
+    CannotExceedCap,
+    LogoTooBig,
+    InvalidXmlPreamble,
+    InvalidPngHeader,
+    InvalidExpiration,
+    DuplicateInitialBalanceAddresses,
+    UnknownChannel,
+    ChannelNotOpen,
+    ChannelNotClosed,
+    CounterpartyNotAllowed,
+    ChainAlreadyAllowed,
+    ChainNotAllowed,
+    NotMainDeployment,
+    UnknownDenom,
+    NotIcs20Channel,
+    ReceiveHookFailed,
+    CallbackFailed,
+    CallbackNotSupported,
+    Ics20Channel,
+    RateLimitExceeded,
+    Paused,
+    IbcReceivePaused,
+    IbcSendPaused,
+    ChannelPaused,
+    NoPendingOwner,
+    NoPendingMinter,
+    MinterProposalExpired,
+    NotSatellite,
+    NotMainChannel,
+    RemoteDenomOnMain,
+    CallbackToSelf,
+    UntrustedController,
+    InvalidRateLimit,
+    RenounceWhilePaused,
+    VoucherBurn,
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the same name as in the JSON of an error ack
+        let name = to_vec(self).map_err(|_| fmt::Error)?;
+        let name = std::str::from_utf8(&name).map_err(|_| fmt::Error)?;
+        f.write_str(name.trim_matches('"'))
+    }
+}
+
+impl ContractError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ContractError::Std(StdError::Overflow { .. }) => ErrorCode::Overflow,
+            ContractError::Std(StdError::ParseErr { .. })
+            | ContractError::Std(StdError::InvalidBase64 { .. })
+            | ContractError::Std(StdError::InvalidUtf8 { .. }) => ErrorCode::InvalidPacket,
+            ContractError::Std(_) => ErrorCode::Std,
+            ContractError::OrderedChannel {} => ErrorCode::OrderedChannel,
+            ContractError::UnorderedControlChannel {} => ErrorCode::UnorderedControlChannel,
+            ContractError::WrongChannelType { .. } => ErrorCode::WrongChannelType,
+            ContractError::InvalidVersion { .. } => ErrorCode::InvalidVersion,
+            ContractError::Unauthorized {} => ErrorCode::Unauthorized,
+            ContractError::CannotSetOwnAccount {} => ErrorCode::CannotSetOwnAccount,
+            ContractError::InvalidZeroAmount {} => ErrorCode::InvalidZeroAmount,
+            ContractError::Expired {} => ErrorCode::Expired,
+            ContractError::NoAllowance {} => ErrorCode::NoAllowance,
+            ContractError::CannotExceedCap {} => ErrorCode::CannotExceedCap,
+            ContractError::LogoTooBig {} => ErrorCode::LogoTooBig,
+            ContractError::InvalidXmlPreamble {} => ErrorCode::InvalidXmlPreamble,
+            ContractError::InvalidPngHeader {} => ErrorCode::InvalidPngHeader,
+            ContractError::InvalidExpiration {} => ErrorCode::InvalidExpiration,
+            ContractError::DuplicateInitialBalanceAddresses {} => {
+                ErrorCode::DuplicateInitialBalanceAddresses
+            }
+            ContractError::UnknownChannel { .. } => ErrorCode::UnknownChannel,
+            ContractError::ChannelNotOpen { .. } => ErrorCode::ChannelNotOpen,
+            ContractError::ChannelNotClosed { .. } => ErrorCode::ChannelNotClosed,
+            ContractError::CounterpartyNotAllowed { .. } => ErrorCode::CounterpartyNotAllowed,
+            ContractError::ChainAlreadyAllowed { .. } => ErrorCode::ChainAlreadyAllowed,
+            ContractError::ChainNotAllowed { .. } => ErrorCode::ChainNotAllowed,
+            ContractError::NotMainDeployment {} => ErrorCode::NotMainDeployment,
+            ContractError::UnknownDenom { .. } => ErrorCode::UnknownDenom,
+            ContractError::NotIcs20Channel { .. } => ErrorCode::NotIcs20Channel,
+            ContractError::ReceiveHookFailed { .. } => ErrorCode::ReceiveHookFailed,
+            ContractError::CallbackFailed { .. } => ErrorCode::CallbackFailed,
+            ContractError::CallbackNotSupported { .. } => ErrorCode::CallbackNotSupported,
+            ContractError::Ics20Channel { .. } => ErrorCode::Ics20Channel,
+            ContractError::RateLimitExceeded { .. } => ErrorCode::RateLimitExceeded,
+            ContractError::Paused {} => ErrorCode::Paused,
+            ContractError::IbcReceivePaused {} => ErrorCode::IbcReceivePaused,
+            ContractError::IbcSendPaused {} => ErrorCode::IbcSendPaused,
+            ContractError::ChannelPaused { .. } => ErrorCode::ChannelPaused,
+            ContractError::NoPendingOwner {} => ErrorCode::NoPendingOwner,
+            ContractError::NoPendingMinter {} => ErrorCode::NoPendingMinter,
+            ContractError::MinterProposalExpired {} => ErrorCode::MinterProposalExpired,
+            ContractError::NotSatellite {} => ErrorCode::NotSatellite,
+            ContractError::NotMainChannel { .. } => ErrorCode::NotMainChannel,
+            ContractError::RemoteDenomOnMain {} => ErrorCode::RemoteDenomOnMain,
+            ContractError::CallbackToSelf {} => ErrorCode::CallbackToSelf,
+            ContractError::UntrustedController { .. } => ErrorCode::UntrustedController,
+            ContractError::InvalidRateLimit {} => ErrorCode::InvalidRateLimit,
+            ContractError::RenounceWhilePaused {} => ErrorCode::RenounceWhilePaused,
+            ContractError::VoucherBurn {} => ErrorCode::VoucherBurn,
+        }
+    }
 }
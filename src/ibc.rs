@@ -1,51 +1,92 @@
 use crate::{
-    ack::{make_ack_fail, make_ack_success},
+    ack::{make_ack_fail, make_ack_success, parse_ack_error, parse_ack_result, Ack, PacketResult},
     allowances::{
         execute_burn_from, execute_decrease_allowance, execute_increase_allowance,
-        execute_send_from, execute_transfer_from,
+        execute_send_from, execute_transfer_from, query_allowance, restore_allowance,
+    },
+    contract::{
+        ensure_channel_open, ensure_from_main_deployment, escrow_or_burn, execute_burn,
+        execute_mint, execute_send, release_or_mint,
     },
-    contract::{execute_burn, execute_mint, execute_send, execute_transfer, try_increment},
     error::Never,
-    msg::IbcExecuteMsg,
-    state::CONNECTION_COUNTS,
+    ics20::{ack_ics20, is_ics20_channel, receive_ics20, timeout_ics20, ICS20_VERSION},
+    msg::{Callback, IbcExecuteMsg, PacketEnvelope, ReceiveCallbackMsg, TransferCallbackMsg},
+    pause::ensure_receive_not_paused,
+    rate_limit::{self, Flow},
+    state::{
+        ChannelInfo, ChannelStats, ChannelStatus, InFlightPacket, PendingMinter, PendingSend,
+        PendingTransfer, BALANCES, CHAINS, CHANNELS, CHANNEL_OUTSTANDING, CHANNEL_STATS,
+        CONTROLLER, IN_FLIGHT, IS_MAIN, PACKET_NONCE, PENDING_MINTER, PENDING_SEND,
+        PENDING_TRANSFER, REMOTE_ACCOUNTS, REMOTE_ALLOWANCES, TOKEN_INFO,
+    },
     ContractError,
 };
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    from_binary, Binary, DepsMut, Env, IbcBasicResponse, IbcChannel, IbcChannelCloseMsg,
-    IbcChannelConnectMsg, IbcChannelOpenMsg, IbcOrder, IbcPacketAckMsg, IbcPacketReceiveMsg,
-    IbcPacketTimeoutMsg, IbcReceiveResponse, MessageInfo, Uint128,
+    from_binary, to_binary, Addr, Api, Binary, CanonicalAddr, DepsMut, Env, Event,
+    Ibc3ChannelOpenResponse, IbcBasicResponse, IbcChannel, IbcChannelCloseMsg,
+    IbcChannelConnectMsg, IbcChannelOpenMsg, IbcChannelOpenResponse, IbcOrder, IbcPacketAckMsg,
+    IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse, MessageInfo, Response, StdError,
+    StdResult, Storage, SubMsg, SubMsgResult, Uint128,
 };
+use cw20::AllowanceResponse;
 use cw_utils::Expiration;
+use sha2::{Digest, Sha256};
 
-pub const IBC_VERSION: &str = "counter-1";
+/// Reply id of the receive hook of a `Send` packet.
+pub const SEND_REPLY_ID: u64 = 1;
+/// Reply id of the callback of a `Transfer` packet.
+pub const TRANSFER_CALLBACK_REPLY_ID: u64 = 2;
+/// Reply id of the callback telling the sender how a transfer ended.
+pub const ACK_CALLBACK_REPLY_ID: u64 = 3;
+
+/// Latest version of the packet protocol, proposed when opening new channels.
+pub const IBC_VERSION: &str = "cw-cross-chain-token-1";
+/// Version of ordered channels carrying control messages, which govern the
+/// token and must arrive in the order they were sent.
+pub const CONTROL_VERSION: &str = "cw-cross-chain-token-control-1";
+/// Every version of the packet protocol this contract can still speak.
+pub const SUPPORTED_VERSIONS: &[&str] = &[IBC_VERSION, ICS20_VERSION, CONTROL_VERSION];
 
 /// Handles the `OpenInit` and `OpenTry` parts of the IBC handshake.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn ibc_channel_open(
-    _deps: DepsMut,
+    deps: DepsMut,
     _env: Env,
     msg: IbcChannelOpenMsg,
-) -> Result<(), ContractError> {
-    validate_order_and_version(msg.channel(), msg.counterparty_version())
+) -> Result<IbcChannelOpenResponse, ContractError> {
+    let version = negotiate_version(msg.channel(), msg.counterparty_version())?;
+    validate_counterparty(deps.storage, msg.channel())?;
+    Ok(Some(Ibc3ChannelOpenResponse { version }))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn ibc_channel_connect(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     msg: IbcChannelConnectMsg,
 ) -> Result<IbcBasicResponse, ContractError> {
     validate_order_and_version(msg.channel(), msg.counterparty_version())?;
+    validate_counterparty(deps.storage, msg.channel())?;
 
-    // Initialize the count for this channel to zero.
     let channel = msg.channel().endpoint.channel_id.clone();
-    CONNECTION_COUNTS.save(deps.storage, channel.clone(), &0)?;
+    let info = ChannelInfo {
+        id: channel.clone(),
+        port_id: msg.channel().endpoint.port_id.clone(),
+        counterparty_port_id: msg.channel().counterparty_endpoint.port_id.clone(),
+        counterparty_channel_id: msg.channel().counterparty_endpoint.channel_id.clone(),
+        connection_id: msg.channel().connection_id.clone(),
+        version: msg.channel().version.clone(),
+        opened_at: env.block.time,
+        status: ChannelStatus::Open,
+    };
+    CHANNELS.save(deps.storage, channel.clone(), &info)?;
 
     Ok(IbcBasicResponse::new()
         .add_attribute("method", "ibc_channel_connect")
-        .add_attribute("channel_id", channel))
+        .add_attribute("channel_id", channel)
+        .add_attribute("version", info.version))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -55,8 +96,16 @@ pub fn ibc_channel_close(
     msg: IbcChannelCloseMsg,
 ) -> Result<IbcBasicResponse, ContractError> {
     let channel = msg.channel().endpoint.channel_id.clone();
-    // Reset the state for the channel.
-    CONNECTION_COUNTS.remove(deps.storage, channel.clone());
+    // Keep the channel around so operators can still see where it led.
+    CHANNELS.update(deps.storage, channel.clone(), |info| -> StdResult<_> {
+        let mut info = info.ok_or_else(|| StdError::not_found("channel"))?;
+        info.status = ChannelStatus::Closed;
+        Ok(info)
+    })?;
+
+    // Transfers still waiting for an ack stay in flight. The counterparty may
+    // already have credited them, only a timeout relayed for the closed
+    // channel proves a packet was never received and refunds it.
     Ok(IbcBasicResponse::new()
         .add_attribute("method", "ibc_channel_close")
         .add_attribute("channel", channel))
@@ -67,35 +116,139 @@ pub fn ibc_packet_receive(
     deps: DepsMut,
     env: Env,
     msg: IbcPacketReceiveMsg,
-    info: MessageInfo,
 ) -> Result<IbcReceiveResponse, Never> {
     // Regardless of if our processing of this packet works we need to
     // commit an ACK to the chain. As such, we wrap all handling logic
     // in a seprate function and on error write out an error ack.
-    match do_ibc_packet_receive(deps, info, env, msg) {
+    let channel = msg.packet.dest.channel_id.clone();
+    let res = update_stats(deps.storage, &channel, |stats| stats.packets_received += 1)
+        .map_err(ContractError::from)
+        .and_then(|()| do_ibc_packet_receive(deps, env, msg));
+    match res {
         Ok(response) => Ok(response),
         Err(error) => Ok(IbcReceiveResponse::new()
             .add_attribute("method", "ibc_packet_receive")
             .add_attribute("error", error.to_string())
-            .set_ack(make_ack_fail(error.to_string()))),
+            .add_attribute("error_code", error.code().to_string())
+            .set_ack(make_ack_fail(&error))),
     }
 }
 
+/// Applies `update` to the packet statistics of `channel`.
+pub fn update_stats(
+    storage: &mut dyn Storage,
+    channel: &str,
+    update: impl FnOnce(&mut ChannelStats),
+) -> StdResult<()> {
+    let mut stats = CHANNEL_STATS
+        .may_load(storage, channel.to_string())?
+        .unwrap_or_default();
+    update(&mut stats);
+    CHANNEL_STATS.save(storage, channel.to_string(), &stats)
+}
+
+/// Wraps `msg` in a `PacketEnvelope` sent on behalf of `sender`, using the
+/// next packet nonce of this contract. Returns the nonce along with the
+/// packet data.
+pub fn make_packet(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: &Addr,
+    msg: IbcExecuteMsg,
+) -> StdResult<(u64, Binary)> {
+    let nonce = PACKET_NONCE.may_load(storage)?.unwrap_or_default() + 1;
+    PACKET_NONCE.save(storage, &nonce)?;
+    let data = to_binary(&PacketEnvelope {
+        sender: sender.to_string(),
+        chain_id: env.block.chain_id.clone(),
+        nonce,
+        msg,
+    })?;
+    Ok((nonce, data))
+}
+
+/// Returns the local account acting for `sender` on the other side of
+/// `channel`. The address is derived from the channel and the remote
+/// sender only, so the same remote sender always maps to the same account
+/// and can't be impersonated from another channel.
+pub fn remote_account(api: &dyn Api, channel: &str, sender: &str) -> StdResult<Addr> {
+    let hash = Sha256::new()
+        .chain_update(channel.as_bytes())
+        .chain_update(b"/")
+        .chain_update(sender.as_bytes())
+        .finalize();
+    api.addr_humanize(&CanonicalAddr::from(hash.as_slice()))
+}
+
 pub fn do_ibc_packet_receive(
-    deps: DepsMut,
-    info: MessageInfo,
+    mut deps: DepsMut,
     env: Env,
     msg: IbcPacketReceiveMsg,
 ) -> Result<IbcReceiveResponse, ContractError> {
+    ensure_receive_not_paused(deps.storage, &msg.packet.dest.channel_id)?;
+    if is_ics20_channel(deps.storage, &msg.packet.dest.channel_id)? {
+        return receive_ics20(deps, env, msg.packet);
+    }
+
     // The channel this packet is being relayed along on this chain.
     let channel = msg.packet.dest.channel_id;
-    let msg: IbcExecuteMsg = from_binary(&msg.packet.data)?;
+    ensure_channel_open(deps.storage, &channel)?;
+    let envelope: PacketEnvelope = from_binary(&msg.packet.data)?;
+    if envelope.msg.is_control() != is_control_channel(deps.storage, &channel)? {
+        return Err(ContractError::WrongChannelType { channel });
+    }
+    if envelope.msg.is_control() {
+        ensure_controller(deps.storage, &channel, &envelope.sender)?;
+    }
+
+    // Everything in the packet is executed by the remote account of the
+    // original sender, never by an address that exists on this chain.
+    let info = MessageInfo {
+        sender: remote_account(deps.api, &channel, &envelope.sender)?,
+        funds: vec![],
+    };
+    let res = dispatch_packet(
+        deps.branch(),
+        env,
+        info.clone(),
+        &envelope.sender,
+        envelope.msg,
+        channel.clone(),
+    )?;
+    // only accounts that executed a packet are listed
+    REMOTE_ACCOUNTS.save(deps.storage, (channel, &info.sender), &envelope.sender)?;
 
+    Ok(res
+        .add_attribute("remote_sender", envelope.sender)
+        .add_attribute("remote_account", info.sender)
+        .add_attribute("source_chain", envelope.chain_id)
+        .add_attribute("nonce", envelope.nonce.to_string()))
+}
+
+fn dispatch_packet(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    remote_sender: &str,
+    msg: IbcExecuteMsg,
+    channel: String,
+) -> Result<IbcReceiveResponse, ContractError> {
     match msg {
-        IbcExecuteMsg::Increment {} => execute_increment(deps, channel),
-        IbcExecuteMsg::Transfer { receipient, amount } => {
-            transfer(deps, env, info, receipient, amount, channel)
-        }
+        IbcExecuteMsg::Transfer {
+            receipient,
+            amount,
+            memo,
+            callback,
+        } => transfer(
+            deps,
+            env,
+            remote_sender,
+            receipient,
+            amount,
+            memo,
+            callback,
+            channel,
+        ),
         IbcExecuteMsg::Burn { amount } => burn(deps, env, info, amount, channel),
         IbcExecuteMsg::TransferFrom {
             owner,
@@ -115,9 +268,7 @@ pub fn do_ibc_packet_receive(
         IbcExecuteMsg::Mint { receipient, amount } => {
             mint(deps, env, info, receipient, amount, channel)
         }
-        IbcExecuteMsg::BurnFrom { owner, amount } => {
-            burn_from(deps, env, info, owner, amount, channel)
-        }
+        IbcExecuteMsg::BurnFrom { owner, amount } => burn_from(deps, env, info, owner, amount),
 
         IbcExecuteMsg::Send {
             contract,
@@ -129,38 +280,164 @@ pub fn do_ibc_packet_receive(
             contract,
             amount,
             msg,
-        } => send_from(deps, env, info, owner, contract, amount, msg, channel),
+        } => send_from(deps, env, info, owner, contract, amount, msg),
+        IbcExecuteMsg::ProposeMinter { new_minter, expiry } => {
+            propose_minter(deps, env, new_minter, expiry, channel)
+        }
+        IbcExecuteMsg::UpdateCap { cap } => update_cap(deps, cap, channel),
     }
 }
 
-fn send_from(
+pub fn is_control_channel(storage: &dyn Storage, channel: &str) -> StdResult<bool> {
+    Ok(CHANNELS
+        .may_load(storage, channel.to_string())?
+        .map(|info| info.version == CONTROL_VERSION)
+        .unwrap_or(false))
+}
+
+/// Control packets govern minting. Anyone on an allowed connection can open a
+/// control channel, so they are only applied by the main deployment, and only
+/// when they come from the configured controller.
+fn ensure_controller(
+    storage: &dyn Storage,
+    channel: &str,
+    sender: &str,
+) -> Result<(), ContractError> {
+    if !IS_MAIN.load(storage)? {
+        return Err(ContractError::NotMainDeployment {});
+    }
+    match CONTROLLER.may_load(storage)? {
+        Some(controller) if controller.channel == channel && controller.sender == sender => Ok(()),
+        _ => Err(ContractError::UntrustedController {
+            channel: channel.to_string(),
+            sender: sender.to_string(),
+        }),
+    }
+}
+
+/// Proposes a minter on behalf of the controller, which goes through the same
+/// handover as `ProposeMinter`. A renounced minter stays renounced.
+fn propose_minter(
     deps: DepsMut,
     env: Env,
+    new_minter: String,
+    expiry: Option<Expiration>,
+    channel: String,
+) -> Result<IbcReceiveResponse, ContractError> {
+    if TOKEN_INFO.load(deps.storage)?.mint.is_none() {
+        return Err(ContractError::Unauthorized {});
+    }
+    let expiry = expiry.unwrap_or_default();
+    if expiry.is_expired(&env.block) {
+        return Err(ContractError::MinterProposalExpired {});
+    }
+    let minter = deps.api.addr_validate(&new_minter)?;
+    PENDING_MINTER.save(
+        deps.storage,
+        &PendingMinter {
+            minter: minter.clone(),
+            expiry,
+        },
+    )?;
+
+    let res = IbcReceiveResponse::new()
+        .add_attribute("method", "propose_minter")
+        .add_attribute("pending_minter", minter.as_str())
+        .add_attribute("expiry", expiry.to_string())
+        .add_attribute("channel", channel);
+    Ok(res.set_ack(make_ack_success(&PacketResult::ProposeMinter {
+        pending_minter: minter.into_string(),
+        expiry,
+    })))
+}
+
+fn update_cap(
+    deps: DepsMut,
+    cap: Option<Uint128>,
+    channel: String,
+) -> Result<IbcReceiveResponse, ContractError> {
+    let mut config = TOKEN_INFO.load(deps.storage)?;
+    if let Some(cap) = cap {
+        if config.total_supply > cap {
+            return Err(ContractError::CannotExceedCap {});
+        }
+    }
+    config
+        .mint
+        .as_mut()
+        .ok_or(ContractError::Unauthorized {})?
+        .cap = cap;
+    TOKEN_INFO.save(deps.storage, &config)?;
+
+    let res = IbcReceiveResponse::new()
+        .add_attribute("method", "update_cap")
+        .add_attribute(
+            "cap",
+            cap.map(|cap| cap.to_string())
+                .unwrap_or_else(|| "None".to_string()),
+        )
+        .add_attribute("channel", channel);
+    Ok(res.set_ack(make_ack_success(&PacketResult::UpdateCap { cap })))
+}
+
+fn balance_of(storage: &dyn Storage, address: &Addr) -> StdResult<Uint128> {
+    Ok(BALANCES.may_load(storage, address)?.unwrap_or_default())
+}
+
+fn total_supply(storage: &dyn Storage) -> StdResult<Uint128> {
+    Ok(TOKEN_INFO.load(storage)?.total_supply)
+}
+
+fn send_from(
+    mut deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     owner: String,
     contract: String,
     amount: Uint128,
     msg: Binary,
-    channel: String,
 ) -> Result<IbcReceiveResponse, ContractError> {
-    execute_send_from(
-        deps,
+    let spender = info.sender.clone();
+    let res = execute_send_from(
+        deps.branch(),
         env,
         info,
         owner.clone(),
         contract.clone(),
         amount,
         msg,
-        channel,
     )?;
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    PENDING_SEND.save(
+        deps.storage,
+        &PendingSend {
+            sender: owner_addr.clone(),
+            contract: deps.api.addr_validate(&contract)?,
+            amount,
+            spender: Some(spender.clone()),
+        },
+    )?;
+    let result = PacketResult::SendFrom {
+        owner: owner.clone(),
+        contract: contract.clone(),
+        owner_balance: balance_of(deps.storage, &owner_addr)?,
+        allowance: query_allowance(deps.as_ref(), owner.clone(), spender.into_string())?.allowance,
+    };
+    // Like for `Send`, a failing receive hook turns into an error ack.
+    let hooks = res
+        .messages
+        .into_iter()
+        .map(|sub| SubMsg::reply_on_error(sub.msg, SEND_REPLY_ID));
     Ok(IbcReceiveResponse::new()
+        .add_submessages(hooks)
         .add_attribute("method", "send_from")
         .add_attribute("owner", owner)
-        .add_attribute("contract", contract.clone())
-        .add_attribute("amount", amount.to_string()))
+        .add_attribute("contract", contract)
+        .add_attribute("amount", amount.to_string())
+        .set_ack(make_ack_success(&result)))
 }
 fn decrease_allowance(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     spender: String,
@@ -168,24 +445,24 @@ fn decrease_allowance(
     expires: Option<Expiration>,
     channel: String,
 ) -> Result<IbcReceiveResponse, ContractError> {
-    execute_increase_allowance(
-        deps,
-        env,
-        info,
-        spender.clone(),
-        amount,
-        expires,
-        channel.clone(),
-    )?;
+    let owner = info.sender.clone();
+    execute_decrease_allowance(deps.branch(), env, info, spender.clone(), amount, expires)?;
+    let allowance = query_allowance(deps.as_ref(), owner.into_string(), spender.clone())?;
+    let result = PacketResult::DecreaseAllowance {
+        spender: spender.clone(),
+        allowance: allowance.allowance,
+        expires: allowance.expires,
+    };
     Ok(IbcReceiveResponse::new()
         .add_attribute("method", "decrease_allowance")
         .add_attribute("spender", spender)
         .add_attribute("amount", amount.to_string())
-        .add_attribute("channel", channel))
+        .add_attribute("channel", channel)
+        .set_ack(make_ack_success(&result)))
 }
 
 fn increase_allowance(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     spender: String,
@@ -193,55 +470,71 @@ fn increase_allowance(
     expires: Option<Expiration>,
     channel: String,
 ) -> Result<IbcReceiveResponse, ContractError> {
-    execute_increase_allowance(
-        deps,
-        env,
-        info,
-        spender.clone(),
-        amount,
-        expires,
-        channel.clone(),
-    )?;
+    let owner = info.sender.clone();
+    execute_increase_allowance(deps.branch(), env, info, spender.clone(), amount, expires)?;
+    let allowance = query_allowance(deps.as_ref(), owner.into_string(), spender.clone())?;
+    let result = PacketResult::IncreaseAllowance {
+        spender: spender.clone(),
+        allowance: allowance.allowance,
+        expires: allowance.expires,
+    };
     Ok(IbcReceiveResponse::new()
         .add_attribute("method", "increase_allowance")
         .add_attribute("spender", spender)
         .add_attribute("amount", amount.to_string())
-        .add_attribute("channel", channel))
+        .add_attribute("channel", channel)
+        .set_ack(make_ack_success(&result)))
 }
 
 fn burn_from(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     owner: String,
     amount: Uint128,
-    channel: String,
 ) -> Result<IbcReceiveResponse, ContractError> {
-    execute_burn_from(deps, env, info, owner.clone(), amount, channel.clone())?;
+    let spender = info.sender.clone();
+    execute_burn_from(deps.branch(), env, info, owner.clone(), amount)?;
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let result = PacketResult::BurnFrom {
+        owner: owner.clone(),
+        owner_balance: balance_of(deps.storage, &owner_addr)?,
+        allowance: query_allowance(deps.as_ref(), owner.clone(), spender.into_string())?.allowance,
+        total_supply: total_supply(deps.storage)?,
+    };
     Ok(IbcReceiveResponse::new()
         .add_attribute("action", "burn_from")
         .add_attribute("from", owner)
-        .add_attribute("amount", amount.to_string()))
+        .add_attribute("amount", amount.to_string())
+        .set_ack(make_ack_success(&result)))
 }
 fn mint(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     recipient: String,
     amount: Uint128,
     channel: String,
 ) -> Result<IbcReceiveResponse, ContractError> {
-    execute_mint(deps, env, info, recipient.clone(), amount, channel.clone())?;
+    rate_limit::check(deps.storage, &env, &channel, Flow::In, amount)?;
+    execute_mint(deps.branch(), env.clone(), info, recipient.clone(), amount)?;
+    rate_limit::record(deps.storage, &env, &channel, Flow::In, amount)?;
+    let rcpt_addr = deps.api.addr_validate(&recipient)?;
+    let result = PacketResult::Mint {
+        recipient: recipient.clone(),
+        balance: balance_of(deps.storage, &rcpt_addr)?,
+        total_supply: total_supply(deps.storage)?,
+    };
     Ok(IbcReceiveResponse::new()
         .add_attribute("method", "mint")
-        .add_attribute("recipient", recipient.to_string())
+        .add_attribute("recipient", recipient)
         .add_attribute("amount", amount.to_string())
-        .add_attribute("channel", channel.to_string())
-        .set_ack(make_ack_success()))
+        .add_attribute("channel", channel)
+        .set_ack(make_ack_success(&result)))
 }
 
 fn send(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     contract: String,
@@ -249,39 +542,57 @@ fn send(
     msg: Binary,
     channel: String,
 ) -> Result<IbcReceiveResponse, ContractError> {
-    execute_send(
-        deps,
-        env,
-        info,
-        contract.clone(),
-        amount,
-        msg,
-        channel.clone(),
+    let sender = info.sender.clone();
+    let res = execute_send(deps.branch(), env, info, contract.clone(), amount, msg)?;
+    PENDING_SEND.save(
+        deps.storage,
+        &PendingSend {
+            sender: sender.clone(),
+            contract: deps.api.addr_validate(&contract)?,
+            amount,
+            spender: None,
+        },
     )?;
+    let result = PacketResult::Send {
+        contract: contract.clone(),
+        balance: balance_of(deps.storage, &sender)?,
+    };
+    // The receive hook runs as a submessage so a failure can be turned into
+    // an error ack instead of a success ack for tokens nobody handled.
+    let hooks = res
+        .messages
+        .into_iter()
+        .map(|sub| SubMsg::reply_on_error(sub.msg, SEND_REPLY_ID));
     Ok(IbcReceiveResponse::new()
+        .add_submessages(hooks)
         .add_attribute("method", "send")
-        .add_attribute("contract", contract.to_string())
+        .add_attribute("contract", contract)
         .add_attribute("amount", amount.to_string())
-        .add_attribute("channel", channel.to_string())
-        .set_ack(make_ack_success()))
+        .add_attribute("channel", channel)
+        .set_ack(make_ack_success(&result)))
 }
 
 fn burn(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     amount: Uint128,
     channel: String,
 ) -> Result<IbcReceiveResponse, ContractError> {
-    let res = execute_burn(deps, env, info, amount, channel.clone())?;
+    let sender = info.sender.clone();
+    execute_burn(deps.branch(), env, info, amount)?;
+    let result = PacketResult::Burn {
+        balance: balance_of(deps.storage, &sender)?,
+        total_supply: total_supply(deps.storage)?,
+    };
     Ok(IbcReceiveResponse::new()
         .add_attribute("method", "execute_burn")
         .add_attribute("amount", amount.to_string())
         .add_attribute("channel", channel)
-        .set_ack(make_ack_success()))
+        .set_ack(make_ack_success(&result)))
 }
 fn transfer_from(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     owner: String,
@@ -289,94 +600,478 @@ fn transfer_from(
     amount: Uint128,
     channel: String,
 ) -> Result<IbcReceiveResponse, ContractError> {
+    let spender = info.sender.clone();
     execute_transfer_from(
-        deps,
+        deps.branch(),
         env,
         info,
         owner.clone(),
         recipient.clone(),
         amount,
-        channel.clone(),
     )?;
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let result = PacketResult::TransferFrom {
+        owner: owner.clone(),
+        recipient: recipient.clone(),
+        owner_balance: balance_of(deps.storage, &owner_addr)?,
+        allowance: query_allowance(deps.as_ref(), owner.clone(), spender.into_string())?.allowance,
+    };
     Ok(IbcReceiveResponse::new()
         .add_attribute("method", "transfer_from")
         .add_attribute("owner", owner)
         .add_attribute("recepient", recipient)
         .add_attribute("amount", amount.to_string())
-        .add_attribute("channel", channel))
+        .add_attribute("channel", channel)
+        .set_ack(make_ack_success(&result)))
 }
+#[allow(clippy::too_many_arguments)]
 fn transfer(
     deps: DepsMut,
     env: Env,
-    info: MessageInfo,
+    remote_sender: &str,
     recipient: String,
     amount: Uint128,
+    memo: Option<String>,
+    callback: Option<Callback>,
     channel: String,
 ) -> Result<IbcReceiveResponse, ContractError> {
-    let res = execute_transfer(deps, env, info, recipient.clone(), amount, channel.clone())?;
-    Ok(IbcReceiveResponse::new()
+    let rcpt_addr = deps.api.addr_validate(&recipient)?;
+    // The callback is executed by this contract, it must neither call the
+    // token itself nor get to choose the message other contracts receive
+    // from it.
+    let callback = match callback {
+        Some(callback) => {
+            let contract = deps.api.addr_validate(&callback.contract)?;
+            if contract == env.contract.address {
+                return Err(ContractError::CallbackToSelf {});
+            }
+            Some((contract, callback.msg))
+        }
+        None => None,
+    };
+    // only counted once the tokens are credited, a failing packet must not
+    // use up the limit
+    rate_limit::check(deps.storage, &env, &channel, Flow::In, amount)?;
+    let outstanding = receive_tokens(deps.storage, &channel, &rcpt_addr, amount)?;
+    rate_limit::record(deps.storage, &env, &channel, Flow::In, amount)?;
+    let result = PacketResult::Transfer {
+        recipient: recipient.clone(),
+        balance: balance_of(deps.storage, &rcpt_addr)?,
+    };
+
+    let mut res = IbcReceiveResponse::new();
+    if let Some(memo) = memo {
+        res = res.add_attribute("memo", memo);
+    }
+    if let Some((contract, msg)) = callback {
+        PENDING_TRANSFER.save(
+            deps.storage,
+            &PendingTransfer {
+                channel: channel.clone(),
+                recipient: rcpt_addr,
+                amount,
+                outstanding,
+            },
+        )?;
+        let msg = ReceiveCallbackMsg {
+            recipient: recipient.clone(),
+            amount,
+            channel: channel.clone(),
+            remote_sender: remote_sender.to_string(),
+            msg,
+        }
+        .into_cosmos_msg(&contract)?;
+        res = res
+            .add_submessage(SubMsg::reply_on_error(msg, TRANSFER_CALLBACK_REPLY_ID))
+            .add_attribute("callback", contract);
+    }
+    Ok(res
         .add_attribute("method", "execute_transfer")
-        .add_attribute("receipient", recipient.to_string())
+        .add_attribute("receipient", recipient)
         .add_attribute("amount", amount.to_string())
-        .add_attribute("channel", channel.to_string())
-        .set_ack(make_ack_success()))
+        .add_attribute("channel", channel)
+        .set_ack(make_ack_success(&result)))
 }
-fn execute_increment(deps: DepsMut, channel: String) -> Result<IbcReceiveResponse, ContractError> {
-    let count = try_increment(deps, channel)?;
-    Ok(IbcReceiveResponse::new()
-        .add_attribute("method", "execute_increment")
-        .add_attribute("count", count.to_string())
-        .set_ack(make_ack_success()))
+
+/// Credits `amount` tokens received over `channel` to `recipient`. The tokens
+/// left the counterparty's supply when it sent the packet, so they are
+/// brought into ours. Returns how much of it was held by the counterparty.
+pub fn receive_tokens(
+    storage: &mut dyn Storage,
+    channel: &str,
+    recipient: &Addr,
+    amount: Uint128,
+) -> Result<Uint128, ContractError> {
+    ensure_from_main_deployment(storage, channel)?;
+    release_or_mint(storage, channel.to_string(), amount)?;
+    // whatever comes back was held by the counterparty until now
+    let outstanding = CHANNEL_OUTSTANDING
+        .may_load(storage, channel.to_string())?
+        .unwrap_or_default();
+    let taken = outstanding.min(amount);
+    CHANNEL_OUTSTANDING.save(storage, channel.to_string(), &(outstanding - taken))?;
+    BALANCES.update(
+        storage,
+        recipient,
+        |balance: Option<Uint128>| -> StdResult<_> { Ok(balance.unwrap_or_default() + amount) },
+    )?;
+    update_stats(storage, channel, |stats| stats.volume_received += amount)?;
+    Ok(taken)
+}
+
+/// Handles the callback of a `Transfer` packet failing. The credit is
+/// undone, the tokens go back out of our supply and the ack becomes an
+/// error ack so the sender gets refunded.
+pub fn reply_transfer_callback(
+    deps: DepsMut,
+    env: Env,
+    result: SubMsgResult,
+) -> Result<Response, ContractError> {
+    // the callback runs with `reply_on_error`, there is only a reply if it failed
+    let error = match result {
+        SubMsgResult::Err(error) => ContractError::CallbackFailed { error },
+        SubMsgResult::Ok(_) => {
+            return Err(StdError::generic_err("Unexpected reply to a successful callback").into())
+        }
+    };
+    let pending = PENDING_TRANSFER.load(deps.storage)?;
+
+    BALANCES.update(
+        deps.storage,
+        &pending.recipient,
+        |balance: Option<Uint128>| -> StdResult<_> {
+            Ok(balance.unwrap_or_default().checked_sub(pending.amount)?)
+        },
+    )?;
+    escrow_or_burn(deps.storage, pending.channel.clone(), pending.amount)?;
+    CHANNEL_OUTSTANDING.update(
+        deps.storage,
+        pending.channel.clone(),
+        |outstanding: Option<Uint128>| -> StdResult<_> {
+            Ok(outstanding.unwrap_or_default() + pending.outstanding)
+        },
+    )?;
+    update_stats(deps.storage, &pending.channel, |stats| {
+        stats.volume_received = stats.volume_received.saturating_sub(pending.amount)
+    })?;
+    rate_limit::unrecord(
+        deps.storage,
+        &env,
+        &pending.channel,
+        Flow::In,
+        pending.amount,
+    )?;
+
+    // Data set by the reply replaces the ack of the packet.
+    Ok(Response::new()
+        .add_attribute("method", "reply_transfer_callback")
+        .add_attribute("error", error.to_string())
+        .add_attribute("error_code", error.code().to_string())
+        .set_data(make_ack_fail(&error)))
+}
+
+/// A failing `ack_callback` must not stop the ack or timeout from being
+/// handled, the error is only reported.
+pub fn reply_ack_callback(result: SubMsgResult) -> Result<Response, ContractError> {
+    let mut res = Response::new().add_attribute("method", "reply_ack_callback");
+    if let SubMsgResult::Err(error) = result {
+        res = res.add_attribute("error", error);
+    }
+    Ok(res)
+}
+
+/// Tells the `ack_callback` contract of `packet`, if any, how the transfer
+/// ended.
+fn ack_callback(packet: &InFlightPacket, error: Option<&str>) -> StdResult<Option<SubMsg>> {
+    let callback = match &packet.callback {
+        Some(callback) => callback,
+        None => return Ok(None),
+    };
+    let msg = TransferCallbackMsg {
+        sender: packet.sender.to_string(),
+        channel: packet.channel.clone(),
+        amount: packet.amount,
+        success: error.is_none(),
+        error: error.map(str::to_string),
+        msg: callback.msg.clone(),
+    }
+    .into_cosmos_msg(callback.contract.clone())?;
+    Ok(Some(SubMsg::reply_on_error(msg, ACK_CALLBACK_REPLY_ID)))
+}
+
+/// Handles the receive hook of a `Send` or `SendFrom` packet failing. The
+/// tokens go back to the account they came from, along with the allowance
+/// spent on them, and the ack becomes an error ack.
+pub fn reply_send(deps: DepsMut, result: SubMsgResult) -> Result<Response, ContractError> {
+    // the hook runs with `reply_on_error`, there is only a reply if it failed
+    let error = match result {
+        SubMsgResult::Err(error) => ContractError::ReceiveHookFailed { error },
+        SubMsgResult::Ok(_) => {
+            return Err(StdError::generic_err("Unexpected reply to a successful hook").into())
+        }
+    };
+    let pending = PENDING_SEND.load(deps.storage)?;
+
+    BALANCES.update(
+        deps.storage,
+        &pending.contract,
+        |balance: Option<Uint128>| -> StdResult<_> {
+            Ok(balance.unwrap_or_default().checked_sub(pending.amount)?)
+        },
+    )?;
+    BALANCES.update(
+        deps.storage,
+        &pending.sender,
+        |balance: Option<Uint128>| -> StdResult<_> {
+            Ok(balance.unwrap_or_default() + pending.amount)
+        },
+    )?;
+    if let Some(spender) = pending.spender {
+        restore_allowance(deps.storage, &pending.sender, &spender, pending.amount)?;
+    }
+
+    // Data set by the reply replaces the ack of the packet.
+    Ok(Response::new()
+        .add_attribute("method", "reply_send")
+        .add_attribute("error", error.to_string())
+        .add_attribute("error_code", error.code().to_string())
+        .set_data(make_ack_fail(&error)))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn ibc_packet_ack(
-    _deps: DepsMut,
-    _env: Env,
-    _ack: IbcPacketAckMsg,
+    deps: DepsMut,
+    env: Env,
+    ack: IbcPacketAckMsg,
 ) -> Result<IbcBasicResponse, ContractError> {
-    // Nothing to do here. We don't keep any state about the other
-    // chain, just deliver messages so nothing to update.
-    //
-    // If we did care about how the other chain received our message
-    // we could deserialize the data field into an `Ack` and inspect
-    // it.
-    Ok(IbcBasicResponse::new().add_attribute("method", "ibc_packet_ack"))
+    let channel = &ack.original_packet.src.channel_id;
+    let result: Ack = from_binary(&ack.acknowledgement.data)?;
+    update_stats(deps.storage, channel, |stats| match result {
+        Ack::Result(_) => stats.acks_ok += 1,
+        Ack::Error(_) => stats.acks_error += 1,
+    })?;
+    if is_ics20_channel(deps.storage, channel)? {
+        let res = ack_ics20(deps, &env, ack.original_packet, result)?;
+        return Ok(res.add_attribute("method", "ibc_packet_ack"));
+    }
+
+    let envelope: PacketEnvelope = from_binary(&ack.original_packet.data)?;
+    let res = match result {
+        Ack::Result(data) => {
+            let mut res = match parse_ack_result(&data) {
+                Some(result) => {
+                    mirror_allowance(deps.storage, channel, &envelope.sender, &result)?;
+                    IbcBasicResponse::new().add_event(result.into_event())
+                }
+                None => IbcBasicResponse::new(),
+            };
+            if let Some(packet) = IN_FLIGHT.may_load(deps.storage, envelope.nonce)? {
+                IN_FLIGHT.remove(deps.storage, envelope.nonce);
+                if let Some(callback) = ack_callback(&packet, None)? {
+                    res = res.add_submessage(callback);
+                }
+            }
+            res
+        }
+        Ack::Error(err) => {
+            let err = parse_ack_error(&err);
+            refund_packet(deps.storage, &env, envelope.nonce, &err.message)?
+                .add_attribute("error_code", err.code.to_string())
+        }
+    };
+    Ok(res
+        .add_attribute("method", "ibc_packet_ack")
+        .add_attribute("nonce", envelope.nonce.to_string()))
+}
+
+/// Updates `REMOTE_ALLOWANCES` from the result of a packet sent by `sender`.
+/// Allowance changes are mirrored for `sender` as owner, spending from a
+/// remote owner for `sender` as spender.
+fn mirror_allowance(
+    storage: &mut dyn Storage,
+    channel: &str,
+    sender: &str,
+    result: &PacketResult,
+) -> StdResult<()> {
+    let (owner, spender, allowance, expires) = match result {
+        PacketResult::IncreaseAllowance {
+            spender,
+            allowance,
+            expires,
+        }
+        | PacketResult::DecreaseAllowance {
+            spender,
+            allowance,
+            expires,
+        } => (sender, spender.as_str(), *allowance, Some(*expires)),
+        PacketResult::TransferFrom {
+            owner, allowance, ..
+        }
+        | PacketResult::SendFrom {
+            owner, allowance, ..
+        }
+        | PacketResult::BurnFrom {
+            owner, allowance, ..
+        } => (owner.as_str(), sender, *allowance, None),
+        _ => return Ok(()),
+    };
+
+    let key = (channel.to_string(), owner.to_string(), spender.to_string());
+    if allowance.is_zero() {
+        REMOTE_ALLOWANCES.remove(storage, key);
+        return Ok(());
+    }
+    REMOTE_ALLOWANCES.update(storage, key, |mirror| -> StdResult<_> {
+        let mut mirror: AllowanceResponse = mirror.unwrap_or_default();
+        mirror.allowance = allowance;
+        // spending doesn't tell when the allowance expires, keep what we know
+        if let Some(expires) = expires {
+            mirror.expires = expires;
+        }
+        Ok(mirror)
+    })?;
+    Ok(())
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn ibc_packet_timeout(
-    _deps: DepsMut,
-    _env: Env,
-    _msg: IbcPacketTimeoutMsg,
+    deps: DepsMut,
+    env: Env,
+    msg: IbcPacketTimeoutMsg,
 ) -> Result<IbcBasicResponse, ContractError> {
-    // As with ack above, nothing to do here. If we cared about
-    // keeping track of state between the two chains then we'd want to
-    // respond to this likely as it means that the packet in question
-    // isn't going anywhere.
-    Ok(IbcBasicResponse::new().add_attribute("method", "ibc_packet_timeout"))
+    // The packet isn't going anywhere, give the tokens back.
+    update_stats(deps.storage, &msg.packet.src.channel_id, |stats| {
+        stats.timeouts += 1
+    })?;
+    if is_ics20_channel(deps.storage, &msg.packet.src.channel_id)? {
+        let res = timeout_ics20(deps, &env, msg.packet)?;
+        return Ok(res.add_attribute("method", "ibc_packet_timeout"));
+    }
+
+    let envelope: PacketEnvelope = from_binary(&msg.packet.data)?;
+    let res = refund_packet(deps.storage, &env, envelope.nonce, "timeout")?;
+    Ok(res
+        .add_attribute("method", "ibc_packet_timeout")
+        .add_attribute("nonce", envelope.nonce.to_string()))
 }
 
-pub fn validate_order_and_version(
-    channel: &IbcChannel,
-    counterparty_version: Option<&str>,
-) -> Result<(), ContractError> {
-    // We expect an unordered channel here. Ordered channels have the
-    // property that if a message is lost the entire channel will stop
-    // working until you start it again.
-    if channel.order != IbcOrder::Unordered {
-        return Err(ContractError::OrderedChannel {});
+/// Gives the tokens of a failed outbound transfer back to its sender.
+/// Packets that didn't escrow anything are ignored.
+fn refund_packet(
+    storage: &mut dyn Storage,
+    env: &Env,
+    nonce: u64,
+    reason: &str,
+) -> Result<IbcBasicResponse, ContractError> {
+    let packet = match IN_FLIGHT.may_load(storage, nonce)? {
+        Some(packet) => packet,
+        None => return Ok(IbcBasicResponse::new()),
+    };
+    IN_FLIGHT.remove(storage, nonce);
+    refund(storage, env, packet, reason)
+}
+
+/// Brings the tokens of `packet` back into our supply and credits them to
+/// its sender. The counterparty never received them, so they no longer count
+/// as held there, nor against the outflow limit of the channel.
+pub fn refund(
+    storage: &mut dyn Storage,
+    env: &Env,
+    packet: InFlightPacket,
+    reason: &str,
+) -> Result<IbcBasicResponse, ContractError> {
+    release_or_mint(storage, packet.channel.clone(), packet.amount)?;
+    rate_limit::release(
+        storage,
+        env,
+        &packet.channel,
+        Flow::Out,
+        packet.amount,
+        packet.sent_at,
+    )?;
+    CHANNEL_OUTSTANDING.update(
+        storage,
+        packet.channel.clone(),
+        |outstanding: Option<Uint128>| -> StdResult<_> {
+            Ok(outstanding
+                .unwrap_or_default()
+                .saturating_sub(packet.amount))
+        },
+    )?;
+    BALANCES.update(
+        storage,
+        &packet.sender,
+        |balance: Option<Uint128>| -> StdResult<_> {
+            Ok(balance.unwrap_or_default() + packet.amount)
+        },
+    )?;
+
+    let mut res = IbcBasicResponse::new();
+    if let Some(callback) = ack_callback(&packet, Some(reason))? {
+        res = res.add_submessage(callback);
     }
+    Ok(res.add_event(
+        Event::new("refund")
+            .add_attribute("to", packet.sender)
+            .add_attribute("channel", packet.channel)
+            .add_attribute("amount", packet.amount)
+            .add_attribute("reason", reason),
+    ))
+}
 
-    if channel.version != IBC_VERSION {
+fn ensure_supported_version(version: &str) -> Result<(), ContractError> {
+    if !SUPPORTED_VERSIONS.contains(&version) {
         return Err(ContractError::InvalidVersion {
-            actual: channel.version.to_string(),
-            expected: IBC_VERSION.to_string(),
+            actual: version.to_string(),
+            expected: SUPPORTED_VERSIONS.join(", "),
         });
     }
+    Ok(())
+}
+
+/// The order of a channel decides its role. Ordered channels have the
+/// property that if a message is lost the entire channel will stop working
+/// until you start it again, so only control channels, whose messages must
+/// not be reordered, are ordered. Data channels are unordered.
+fn ensure_order(order: &IbcOrder, version: &str) -> Result<(), ContractError> {
+    match (order, version == CONTROL_VERSION) {
+        (IbcOrder::Ordered, false) => Err(ContractError::OrderedChannel {}),
+        (IbcOrder::Unordered, true) => Err(ContractError::UnorderedControlChannel {}),
+        _ => Ok(()),
+    }
+}
+
+/// Picks the protocol version for a channel being opened. On `OpenTry` we
+/// follow the counterparty if we support its version, on `OpenInit` we
+/// take the proposed version, or the latest one for the order of the
+/// channel if none was proposed.
+pub fn negotiate_version(
+    channel: &IbcChannel,
+    counterparty_version: Option<&str>,
+) -> Result<String, ContractError> {
+    let latest = match channel.order {
+        IbcOrder::Ordered => CONTROL_VERSION,
+        IbcOrder::Unordered => IBC_VERSION,
+    };
+    let version = match counterparty_version {
+        Some(counterparty_version) => counterparty_version,
+        None if channel.version.is_empty() => latest,
+        None => &channel.version,
+    };
+    ensure_supported_version(version)?;
+    ensure_order(&channel.order, version)?;
+    Ok(version.to_string())
+}
 
-    // Make sure that we're talking with a counterparty who speaks the
-    // same "protocol" as us.
+pub fn validate_order_and_version(
+    channel: &IbcChannel,
+    counterparty_version: Option<&str>,
+) -> Result<(), ContractError> {
+    ensure_supported_version(&channel.version)?;
+    ensure_order(&channel.order, &channel.version)?;
+
+    // Make sure that both ends agreed on the same version of the
+    // protocol.
     //
     // For a connection between chain A and chain B being established
     // by chain A, chain B knows counterparty information during
@@ -384,13 +1079,32 @@ pub fn validate_order_and_version(
     // `OpenAck`. We verify it when we have it but when we don't it's
     // alright.
     if let Some(counterparty_version) = counterparty_version {
-        if counterparty_version != IBC_VERSION {
+        if counterparty_version != channel.version {
             return Err(ContractError::InvalidVersion {
                 actual: counterparty_version.to_string(),
-                expected: IBC_VERSION.to_string(),
+                expected: channel.version.to_string(),
             });
         }
     }
 
     Ok(())
 }
+
+/// Checks the counterparty against the `other_chains` allowlist. The chain id
+/// of the counterparty isn't known during the handshake, so a chain is
+/// identified either by its port id or by the connection the channel is
+/// opened on.
+pub fn validate_counterparty(
+    storage: &dyn Storage,
+    channel: &IbcChannel,
+) -> Result<(), ContractError> {
+    let chains = CHAINS.load(storage)?;
+    let port_id = &channel.counterparty_endpoint.port_id;
+    if !chains.is_allowed(port_id.clone())? && !chains.is_allowed(channel.connection_id.clone())? {
+        return Err(ContractError::CounterpartyNotAllowed {
+            port_id: port_id.clone(),
+            connection_id: channel.connection_id.clone(),
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,174 @@
+use cosmwasm_std::{
+    from_binary, Deps, DepsMut, Env, IbcBasicResponse, IbcPacket, IbcReceiveResponse, StdError,
+    StdResult, Storage, Uint128,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    ack::{make_ack_ics20_success, parse_ack_error, Ack},
+    contract::ensure_channel_open,
+    ibc::{receive_tokens, refund},
+    msg::DenomTraceResponse,
+    rate_limit::{self, Flow},
+    state::{InFlightPacket, CHANNELS, REMOTE_DENOMS},
+    ContractError,
+};
+
+/// Version of channels speaking the ICS-20 fungible token transfer protocol,
+/// which lets the token be sent to chains running the transfer module.
+pub const ICS20_VERSION: &str = "ics20-1";
+
+/// `FungibleTokenPacketData` of ICS-20. See:
+/// https://github.com/cosmos/ibc/tree/main/spec/app/ics-020-fungible-token-transfer
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Ics20Packet {
+    /// Denom of the token including its trace, `port/channel/` prefixes for
+    /// every hop it took away from its origin.
+    pub denom: String,
+    pub amount: Uint128,
+    pub sender: String,
+    pub receiver: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// Denom of this token on ICS-20 channels, the same as used by cw20-ics20.
+pub fn local_denom(env: &Env) -> String {
+    format!("cw20:{}", env.contract.address)
+}
+
+/// Denom of the tokens sent over an ICS-20 `channel`. Vouchers of a token
+/// originating on the other side go back with their trace, so the
+/// counterparty recognizes them as its own.
+pub fn ics20_denom(storage: &dyn Storage, env: &Env, channel: &str) -> StdResult<String> {
+    match REMOTE_DENOMS.may_load(storage, channel.to_string())? {
+        Some(denom) => {
+            let info = CHANNELS.load(storage, channel.to_string())?;
+            Ok(format!("{}/{}/{}", info.port_id, channel, denom))
+        }
+        None => Ok(local_denom(env)),
+    }
+}
+
+/// Denom the transfer module gives vouchers received with `denom_trace`.
+fn ibc_denom(denom_trace: &str) -> String {
+    let hash = Sha256::digest(denom_trace.as_bytes());
+    let hex: String = hash.iter().map(|byte| format!("{:02X}", byte)).collect();
+    format!("ibc/{}", hex)
+}
+
+pub fn query_denom_trace(deps: Deps, env: Env, channel: String) -> StdResult<DenomTraceResponse> {
+    if !is_ics20_channel(deps.storage, &channel)? {
+        return Err(StdError::generic_err(
+            ContractError::NotIcs20Channel { channel }.to_string(),
+        ));
+    }
+    let info = CHANNELS.load(deps.storage, channel.clone())?;
+    let (path, base_denom, held_here) = match REMOTE_DENOMS.may_load(deps.storage, channel)? {
+        Some(denom) => (format!("{}/{}", info.port_id, info.id), denom, true),
+        None => (
+            format!(
+                "{}/{}",
+                info.counterparty_port_id, info.counterparty_channel_id
+            ),
+            local_denom(&env),
+            false,
+        ),
+    };
+    Ok(DenomTraceResponse {
+        ibc_denom: ibc_denom(&format!("{}/{}", path, base_denom)),
+        path,
+        base_denom,
+        held_here,
+    })
+}
+
+pub fn is_ics20_channel(storage: &dyn Storage, channel: &str) -> StdResult<bool> {
+    Ok(CHANNELS
+        .may_load(storage, channel.to_string())?
+        .map(|info| info.version == ICS20_VERSION)
+        .unwrap_or(false))
+}
+
+/// Credits tokens received over an ICS-20 channel. Only this token is
+/// accepted, either coming back over the channel it left on, in which case
+/// the counterparty prefixed the denom with its own port and channel, or as
+/// the remote denom set for the channel when it originates on the other side.
+pub fn receive_ics20(
+    deps: DepsMut,
+    env: Env,
+    packet: IbcPacket,
+) -> Result<IbcReceiveResponse, ContractError> {
+    let channel = packet.dest.channel_id;
+    ensure_channel_open(deps.storage, &channel)?;
+    let data: Ics20Packet = from_binary(&packet.data)?;
+
+    let denom = format!(
+        "{}/{}/{}",
+        packet.src.port_id,
+        packet.src.channel_id,
+        local_denom(&env)
+    );
+    let remote_denom = REMOTE_DENOMS.may_load(deps.storage, channel.clone())?;
+    if data.denom != denom && Some(&data.denom) != remote_denom.as_ref() {
+        return Err(ContractError::UnknownDenom { denom: data.denom });
+    }
+
+    let recipient = deps.api.addr_validate(&data.receiver)?;
+    rate_limit::check(deps.storage, &env, &channel, Flow::In, data.amount)?;
+    receive_tokens(deps.storage, &channel, &recipient, data.amount)?;
+    rate_limit::record(deps.storage, &env, &channel, Flow::In, data.amount)?;
+
+    let mut res = IbcReceiveResponse::new();
+    if let Some(memo) = data.memo {
+        res = res.add_attribute("memo", memo);
+    }
+    Ok(res
+        .add_attribute("method", "receive_ics20")
+        .add_attribute("sender", data.sender)
+        .add_attribute("receiver", data.receiver)
+        .add_attribute("amount", data.amount)
+        .add_attribute("channel", channel)
+        .set_ack(make_ack_ics20_success()))
+}
+
+/// ICS-20 packets carry no nonce, so the transfer to refund is
+/// read back from the packet itself.
+fn in_flight_packet(deps: &DepsMut, packet: &IbcPacket) -> StdResult<InFlightPacket> {
+    let data: Ics20Packet = from_binary(&packet.data)?;
+    Ok(InFlightPacket {
+        sender: deps.api.addr_validate(&data.sender)?,
+        amount: data.amount,
+        channel: packet.src.channel_id.clone(),
+        callback: None,
+        sent_at: None,
+    })
+}
+
+pub fn ack_ics20(
+    deps: DepsMut,
+    env: &Env,
+    packet: IbcPacket,
+    ack: Ack,
+) -> Result<IbcBasicResponse, ContractError> {
+    match ack {
+        Ack::Result(_) => Ok(IbcBasicResponse::new()),
+        Ack::Error(err) => {
+            let in_flight = in_flight_packet(&deps, &packet)?;
+            let err = parse_ack_error(&err);
+            Ok(refund(deps.storage, env, in_flight, &err.message)?
+                .add_attribute("error_code", err.code.to_string()))
+        }
+    }
+}
+
+pub fn timeout_ics20(
+    deps: DepsMut,
+    env: &Env,
+    packet: IbcPacket,
+) -> Result<IbcBasicResponse, ContractError> {
+    let in_flight = in_flight_packet(&deps, &packet)?;
+    refund(deps.storage, env, in_flight, "timeout")
+}
@@ -4,7 +4,13 @@ pub mod contract;
 pub mod enumerable;
 mod error;
 pub mod ibc;
+pub mod ics20;
 pub mod msg;
+pub mod pause;
+pub mod rate_limit;
 pub mod state;
 
-pub use crate::error::ContractError;
+pub use crate::error::{ContractError, ErrorCode};
+
+#[cfg(test)]
+mod testing;
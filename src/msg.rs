@@ -1,17 +1,26 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Binary, StdError, StdResult, Uint128};
+use cosmwasm_std::{
+    to_binary, Binary, CosmosMsg, StdError, StdResult, Timestamp, Uint128, WasmMsg,
+};
 use cw20::{Cw20Coin, Logo, MinterResponse};
 use cw_utils::Expiration;
+
+use crate::state::{ChannelInfo, Controller, MainDeployment, Ownership, RateLimit};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum IbcExecuteMsg {
-    Increment {},
     Transfer {
         receipient: String,
         amount: Uint128,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        memo: Option<String>,
+        /// Executed on the destination with a `ReceiveCallbackMsg` after crediting
+        /// the recipient. The transfer fails with it.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        callback: Option<Callback>,
     },
     Burn {
         amount: Uint128,
@@ -55,13 +64,349 @@ pub enum IbcExecuteMsg {
         owner: String,
         amount: Uint128,
     },
+    /// Only over control channels. Proposes a new minter the same way the
+    /// current minter can, the minter only changes once the proposed one
+    /// accepts.
+    ProposeMinter {
+        new_minter: String,
+        expiry: Option<Expiration>,
+    },
+    /// Only over control channels. Sets how many tokens the minter may issue in
+    /// total, `None` removes the cap.
+    UpdateCap {
+        cap: Option<Uint128>,
+    },
+}
+
+impl IbcExecuteMsg {
+    /// Whether the message governs the token and travels over control channels.
+    pub fn is_control(&self) -> bool {
+        matches!(
+            self,
+            IbcExecuteMsg::ProposeMinter { .. } | IbcExecuteMsg::UpdateCap { .. }
+        )
+    }
+}
+
+/// Contract to execute with `msg`, without funds.
+#[cw_serde]
+pub struct Callback {
+    pub contract: String,
+    pub msg: Binary,
+}
+
+/// Sent to the `ack_callback` contract of a `TransferRemote` once the transfer
+/// was acknowledged, failed or timed out.
+#[cw_serde]
+pub struct TransferCallbackMsg {
+    pub sender: String,
+    pub channel: String,
+    pub amount: Uint128,
+    /// Whether the tokens arrived. If not they were refunded to the sender.
+    pub success: bool,
+    pub error: Option<String>,
+    pub msg: Binary,
+}
+
+impl TransferCallbackMsg {
+    /// serializes the message
+    pub fn into_binary(self) -> StdResult<Binary> {
+        let msg = CallbackExecuteMsg::TransferCallback(self);
+        to_binary(&msg)
+    }
+
+    /// creates a cosmos_msg sending this struct to the named contract
+    pub fn into_cosmos_msg<T: Into<String>>(self, contract_addr: T) -> StdResult<CosmosMsg> {
+        let msg = self.into_binary()?;
+        let execute = WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg,
+            funds: vec![],
+        };
+        Ok(execute.into())
+    }
+}
+
+/// Sent to the `callback` contract of a transfer on the destination, once the
+/// recipient was credited. The message from the sender is only passed along
+/// inside it, so it can't pose as anything this token sends, like a
+/// `Cw20ReceiveMsg`.
+#[cw_serde]
+pub struct ReceiveCallbackMsg {
+    pub recipient: String,
+    pub amount: Uint128,
+    pub channel: String,
+    /// Sender of the transfer on the source chain.
+    pub remote_sender: String,
+    pub msg: Binary,
+}
+
+impl ReceiveCallbackMsg {
+    /// serializes the message
+    pub fn into_binary(self) -> StdResult<Binary> {
+        let msg = CallbackExecuteMsg::ReceiveCallback(self);
+        to_binary(&msg)
+    }
+
+    /// creates a cosmos_msg sending this struct to the named contract
+    pub fn into_cosmos_msg<T: Into<String>>(self, contract_addr: T) -> StdResult<CosmosMsg> {
+        let msg = self.into_binary()?;
+        let execute = WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg,
+            funds: vec![],
+        };
+        Ok(execute.into())
+    }
+}
+
+// This is just a helper to properly serialize the above messages
+#[cw_serde]
+enum CallbackExecuteMsg {
+    TransferCallback(TransferCallbackMsg),
+    ReceiveCallback(ReceiveCallbackMsg),
+}
+
+/// Envelope every packet sent by this contract is wrapped in. It carries the
+/// account that triggered the packet on the source chain so the receiving
+/// side can act on its behalf.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct PacketEnvelope {
+    /// Address of the sender on the source chain.
+    pub sender: String,
+    /// Chain id of the source chain.
+    pub chain_id: String,
+    /// Unique, increasing number assigned by the source contract.
+    pub nonce: u64,
+    pub msg: IbcExecuteMsg,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Transfer is a base message to move tokens to another account without triggering actions
+    Transfer { recipient: String, amount: Uint128 },
+    /// Burn is a base message to destroy tokens forever. Only on the main deployment.
+    Burn { amount: Uint128 },
+    /// Send is a base message to transfer tokens to a contract and trigger an action
+    /// on the receiving contract.
+    Send {
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+    /// Only with "approval" extension. Allows spender to access an additional amount tokens
+    /// from the owner's (env.sender) account. If expires is Some(), overwrites current allowance
+    /// expiration with this one.
+    IncreaseAllowance {
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+    /// Only with "approval" extension. Lowers the spender's access of tokens
+    /// from the owner's (env.sender) account by amount. If expires is Some(), overwrites current
+    /// allowance expiration with this one.
+    DecreaseAllowance {
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+    },
+    /// Only with "approval" extension. Transfers amount tokens from owner -> recipient
+    /// if `env.sender` has sufficient pre-approval.
+    TransferFrom {
+        owner: String,
+        recipient: String,
+        amount: Uint128,
+    },
+    /// Only with "approval" extension. Sends amount tokens from owner -> contract
+    /// if `env.sender` has sufficient pre-approval.
+    SendFrom {
+        owner: String,
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+    },
+    /// Only with "approval" extension. Destroys tokens forever. Only on the main deployment.
+    BurnFrom { owner: String, amount: Uint128 },
+    /// Only with the "mintable" extension. If authorized, creates amount new tokens
+    /// and adds to the recipient balance.
+    Mint { recipient: String, amount: Uint128 },
+    /// Only with the "mintable" extension. The current minter may propose
+    /// a new minter, replacing any earlier proposal. The minter only changes
+    /// once the proposed one accepts, before `expiry` if set.
+    ProposeMinter {
+        new_minter: String,
+        expiry: Option<Expiration>,
+    },
+    /// Only with the "mintable" extension. The proposed minter takes over
+    /// minting.
+    AcceptMinter {},
+    /// Only with the "mintable" extension. The current minter withdraws its
+    /// proposal.
+    CancelMinterProposal {},
+    /// Only with the "mintable" extension. The current minter removes the
+    /// token's minter forever.
+    RenounceMinter {},
+    /// Only with the "marketing" extension. If authorized, updates marketing metadata.
+    /// Setting None/null for any of these will leave it unchanged.
+    /// Setting Some("") will clear this field on the contract storage
+    UpdateMarketing {
+        /// A URL pointing to the project behind this token.
+        project: Option<String>,
+        /// A longer description of the token and it's utility. Designed for tooltips or such
+        description: Option<String>,
+        /// The address (if any) who can update this data structure
+        marketing: Option<String>,
+    },
+    /// If set as the "marketing" role on the contract, upload a new URL, SVG, or PNG for the token
+    UploadLogo(Logo),
+    /// Takes amount tokens out of the sender's balance and sends them over `channel`
+    /// to `recipient` on the counterparty chain. On ICS-20 channels they arrive as
+    /// an IBC denom.
+    /// `timeout` is in seconds and defaults to `DEFAULT_TIMEOUT_SECONDS`.
+    /// `callback` is executed on the destination with a `ReceiveCallbackMsg` after
+    /// crediting the recipient, `ack_callback` here with a `TransferCallbackMsg`
+    /// once the transfer resolved.
+    /// Callbacks need a counterparty running this contract, not an ICS-20 channel.
+    TransferRemote {
+        channel: String,
+        recipient: String,
+        amount: Uint128,
+        timeout: Option<u64>,
+        memo: Option<String>,
+        callback: Option<Callback>,
+        ack_callback: Option<Callback>,
+    },
+    /// Only the owner. Allows channels to be opened with `chain`, matched against the
+    /// counterparty port id or the connection id during the handshake.
+    AddChain { chain: String },
+    /// Only the owner. Stops new channels from being opened with `chain`.
+    /// Channels that are already open are not affected.
+    RemoveChain { chain: String },
+    /// Only the owner. Starts closing an open channel, it is marked as closing
+    /// until the counterparty confirms.
+    CloseChannel { channel: String },
+    /// Only the owner. Moves the balance of the account acting for `remote_sender`
+    /// on a closed `channel` to `recipient`, as the remote sender can no longer
+    /// reach it. This is custodial: the remote sender can't prove who they are
+    /// once the channel is gone, so the owner decides where the balance goes.
+    Redeem {
+        channel: String,
+        remote_sender: String,
+        recipient: String,
+    },
+    /// Only the owner, only on satellites. Sets the denom the token has on the other
+    /// side of an ICS-20 `channel` when it originates there, e.g. `cw20:<address>`
+    /// for a cw20-ics20 counterparty, which then is the main deployment. Tokens
+    /// received with that denom are credited as vouchers and sent back with their
+    /// trace.
+    SetRemoteDenom { channel: String, denom: String },
+    /// Only the owner, only on satellites. Sets the counterparty vouchers are
+    /// minted for, channels to anywhere else can't bring tokens in.
+    SetMainDeployment { main_deployment: MainDeployment },
+    /// Allows `spender` on the counterparty of `channel` to spend tokens of the
+    /// sender's account there. Mirrored locally once acknowledged.
+    IncreaseRemoteAllowance {
+        channel: String,
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+        timeout: Option<u64>,
+    },
+    /// Lowers the allowance of `spender` on the counterparty of `channel`.
+    DecreaseRemoteAllowance {
+        channel: String,
+        spender: String,
+        amount: Uint128,
+        expires: Option<Expiration>,
+        timeout: Option<u64>,
+    },
+    /// Transfers tokens of `owner` on the counterparty of `channel` to `recipient`
+    /// there, using the allowance `owner` granted to the sender's account.
+    TransferFromRemote {
+        channel: String,
+        owner: String,
+        recipient: String,
+        amount: Uint128,
+        timeout: Option<u64>,
+    },
+    /// Sends tokens of `owner` on the counterparty of `channel` to `contract` there,
+    /// using the allowance `owner` granted to the sender's account.
+    SendFromRemote {
+        channel: String,
+        owner: String,
+        contract: String,
+        amount: Uint128,
+        msg: Binary,
+        timeout: Option<u64>,
+    },
+    /// Burns tokens of `owner` on the counterparty of `channel`, using the
+    /// allowance `owner` granted to the sender's account.
+    BurnFromRemote {
+        channel: String,
+        owner: String,
+        amount: Uint128,
+        timeout: Option<u64>,
+    },
+    /// Only the owner. Proposes a new minter to the deployment on the other side
+    /// of the control `channel`, which only applies it if it is the main
+    /// deployment and this contract's owner is its controller. The proposed
+    /// minter still has to accept there.
+    ProposeRemoteMinter {
+        channel: String,
+        new_minter: String,
+        expiry: Option<Expiration>,
+        timeout: Option<u64>,
+    },
+    /// Only the owner. Sets the minting cap of the deployment on the other side of
+    /// the control `channel`, under the same conditions as `ProposeRemoteMinter`.
+    UpdateRemoteCap {
+        channel: String,
+        cap: Option<Uint128>,
+        timeout: Option<u64>,
+    },
+    /// Only the owner, only on the main deployment. Sets the only sender whose
+    /// control packets are applied, and the control channel they must arrive
+    /// over. `None` stops applying control packets.
+    SetController { controller: Option<Controller> },
+    /// Only the owner. Limits how many tokens can be received (`inflow`) and sent
+    /// (`outflow`) over `channel` during any window of time. `None` lifts the limit.
+    SetRateLimits {
+        channel: String,
+        inflow: Option<RateLimit>,
+        outflow: Option<RateLimit>,
+    },
+    /// Only the owner. Sets or removes the guardian, who may pause the
+    /// contract alongside the owner.
+    SetGuardian { guardian: Option<String> },
+    /// Only the owner or the guardian. Stops processing `scope` until it is
+    /// unpaused, packets received while paused are answered with an error ack.
+    Pause { scope: PauseScope },
+    /// Only the owner. Resumes processing `scope`.
+    Unpause { scope: PauseScope },
+    /// Only the owner. Proposes `new_owner` to take over the contract, replacing
+    /// any earlier proposal. Ownership only moves once it is accepted.
+    ProposeOwner { new_owner: String },
+    /// Only the pending owner. Takes over ownership of the contract.
+    AcceptOwner {},
+    /// Only the owner, only while nothing is paused. Gives up ownership for good,
+    /// leaving no one able to run owner operations, and removes the guardian.
+    RenounceOwnership {},
 }
-// We define a custom struct for each query response
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
-pub struct GetCountResponse {
-    pub count: u32,
+
+#[cw_serde]
+pub enum PauseScope {
+    /// Every execute message apart from pausing and ownership, and all received
+    /// packets.
+    /// Acks and timeouts of packets already sent are still processed.
+    All,
+    /// Packets received over any channel.
+    IbcReceive,
+    /// Packets sent over any channel.
+    IbcSend,
+    /// Packets sent or received over `channel`, which must be known to pause it.
+    Channel { channel: String },
 }
-pub use cw20::Cw20ExecuteMsg as ExecuteMsg;
 
 #[cw_serde]
 pub struct InstantiateMarketingInfo {
@@ -82,6 +427,11 @@ pub struct InstantiateMsg {
     pub other_chains: Vec<String>,
     pub mint: Option<MinterResponse>,
     pub marketing: Option<InstantiateMarketingInfo>,
+    /// Owner of the contract, defaults to the instantiating address.
+    pub owner: Option<String>,
+    /// Only for satellites. Counterparty tokens are accepted from, without it a
+    /// satellite receives nothing until `SetMainDeployment` is called.
+    pub main_deployment: Option<MainDeployment>,
 }
 
 impl InstantiateMsg {
@@ -133,27 +483,26 @@ impl InstantiateMsg {
 pub enum QueryMsg {
     /// Returns the current balance of the given address, 0 if unset.
     #[returns(cw20::BalanceResponse)]
-    Balance { channel: String, address: String },
+    Balance { address: String },
     /// Returns metadata on the contract - name, decimals, supply, etc.
     #[returns(cw20::TokenInfoResponse)]
-    TokenInfo { channel: String },
+    TokenInfo {},
     /// Only with "mintable" extension.
     /// Returns who can mint and the hard cap on maximum tokens after minting.
     #[returns(cw20::MinterResponse)]
-    Minter { channel: String },
+    Minter {},
+    /// Only with "mintable" extension.
+    /// Returns the minter proposed to take over, if any.
+    #[returns(Option<PendingMinterResponse>)]
+    PendingMinter {},
     /// Only with "allowance" extension.
     /// Returns how much spender can use from owner account, 0 if unset.
     #[returns(cw20::AllowanceResponse)]
-    Allowance {
-        channel: String,
-        owner: String,
-        spender: String,
-    },
+    Allowance { owner: String, spender: String },
     /// Only with "enumerable" extension (and "allowances")
     /// Returns all allowances this owner has approved. Supports pagination.
     #[returns(cw20::AllAllowancesResponse)]
     AllAllowances {
-        channel: String,
         owner: String,
         start_after: Option<String>,
         limit: Option<u32>,
@@ -162,18 +511,27 @@ pub enum QueryMsg {
     /// Returns all allowances this spender has been granted. Supports pagination.
     #[returns(cw20::AllSpenderAllowancesResponse)]
     AllSpenderAllowances {
-        channel: String,
         spender: String,
         start_after: Option<String>,
         limit: Option<u32>,
     },
     /// Only with "enumerable" extension
     /// Returns all accounts that have balances. Supports pagination.
-    //#[returns(cw20::AllAccountsResponse)]
-    //AllAccounts {
-    //    start_after: Option<String>,
-    //    limit: Option<u32>,
-    //},
+    #[returns(cw20::AllAccountsResponse)]
+    AllAccounts {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Only with "enumerable" extension
+    /// Returns accounts along with their balance. Without a channel this lists the
+    /// whole ledger, with a channel only the remote accounts acting for senders on
+    /// that channel. Supports pagination.
+    #[returns(AllBalancesResponse)]
+    AllBalances {
+        channel: Option<String>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     /// Only with "marketing" extension
     /// Returns more metadata on the contract to display in the client:
     /// - description, logo, project url, etc.
@@ -184,12 +542,138 @@ pub enum QueryMsg {
     /// contract.
     #[returns(cw20::DownloadLogoResponse)]
     DownloadLogo {},
-    // GetCount returns the current count as a json-encoded number
-    #[returns(u32)]
-    GetCount {
-        // The ID of the channel you'd like to query the count for.
+    /// Returns the channels linking this token to remote deployments. Supports pagination.
+    #[returns(ListChannelsResponse)]
+    ListChannels {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the counterparty, version and status of a channel.
+    #[returns(crate::state::ChannelInfo)]
+    Channel { id: String },
+    /// Returns the local supply and how much of the token is escrowed, in flight and
+    /// held by the counterparty on each channel.
+    #[returns(SupplyBreakdownResponse)]
+    SupplyBreakdown {},
+    /// Returns how many packets went over a channel and how they ended, along with
+    /// the volume sent and received.
+    #[returns(crate::state::ChannelStats)]
+    ChannelStats { channel: String },
+    /// Returns the denom trace of the vouchers of this token over an ICS-20 channel.
+    #[returns(DenomTraceResponse)]
+    DenomTrace { channel: String },
+    /// Returns the allowance `owner` granted `spender` on the counterparty of `channel`,
+    /// as last acknowledged. One of them is a local address, the other one lives on the
+    /// counterparty.
+    #[returns(cw20::AllowanceResponse)]
+    RemoteAllowance {
         channel: String,
+        owner: String,
+        spender: String,
     },
+    /// Returns the rate limits of a channel and how much of them is used.
+    #[returns(RateLimitUsageResponse)]
+    RateLimitUsage { channel: String },
+    /// Only on satellites. Returns the counterparty tokens are accepted from.
+    #[returns(Option<MainDeployment>)]
+    MainDeployment {},
+    /// Returns the source of the control packets this deployment applies, if any.
+    #[returns(Option<Controller>)]
+    Controller {},
+    /// Returns the owner and the pending owner, if any.
+    #[returns(Ownership)]
+    Ownership {},
+    /// Returns what is paused.
+    #[returns(PauseStatusResponse)]
+    PauseStatus {},
+}
+
+#[cw_serde]
+pub struct AccountBalance {
+    pub address: String,
+    pub balance: Uint128,
+    /// Sender on the counterparty chain, set when listing the accounts of a channel.
+    pub remote_sender: Option<String>,
+}
+
+#[cw_serde]
+pub struct AllBalancesResponse {
+    pub balances: Vec<AccountBalance>,
+}
+
+#[cw_serde]
+pub struct PendingMinterResponse {
+    pub pending_minter: String,
+    pub expiry: Expiration,
+}
+
+#[cw_serde]
+pub struct PauseStatusResponse {
+    pub all: bool,
+    pub ibc_receive: bool,
+    pub ibc_send: bool,
+    /// Channels paused on their own.
+    pub channels: Vec<String>,
+    pub guardian: Option<String>,
+}
+
+#[cw_serde]
+pub struct FlowUsage {
+    pub limit: RateLimit,
+    /// Tokens counted against the limit, moved during the window ending now.
+    pub used: Uint128,
+    /// Tokens that can still move right now.
+    pub available: Uint128,
+    /// When the oldest of the counted tokens stop counting, unset if none are.
+    pub next_release: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub struct RateLimitUsageResponse {
+    /// Unset if inflows aren't limited.
+    pub inflow: Option<FlowUsage>,
+    /// Unset if outflows aren't limited.
+    pub outflow: Option<FlowUsage>,
+}
+
+#[cw_serde]
+pub struct DenomTraceResponse {
+    /// `port/channel` the vouchers were received over, on the chain holding them.
+    pub path: String,
+    /// Denom of the token on the chain it originates on.
+    pub base_denom: String,
+    /// `ibc/` denom of the vouchers on a chain running the transfer module.
+    pub ibc_denom: String,
+    /// Whether the vouchers are held on this chain, or on the counterparty.
+    pub held_here: bool,
+}
+
+#[cw_serde]
+pub struct ListChannelsResponse {
+    pub channels: Vec<ChannelInfo>,
+}
+
+#[cw_serde]
+pub struct ChannelAmount {
+    pub channel: String,
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct SupplyBreakdownResponse {
+    pub is_main: bool,
+    /// Tokens circulating on this chain, the token's `total_supply` minus escrowed tokens.
+    pub local_supply: Uint128,
+    /// Tokens escrowed per outbound channel. Only the main deployment escrows.
+    pub escrowed: Vec<ChannelAmount>,
+    /// Vouchers minted per inbound channel. Only satellite deployments mint vouchers.
+    pub vouchers: Vec<ChannelAmount>,
+    /// Tokens sent per channel that haven't been acknowledged or timed out yet.
+    /// Transfers over ICS-20 channels are not tracked here.
+    pub in_flight: Vec<ChannelAmount>,
+    /// Tokens sent to the counterparty on each channel and not sent back, including
+    /// the ones still in flight.
+    pub outstanding: Vec<ChannelAmount>,
 }
 
 #[derive(Serialize, Deserialize, JsonSchema)]
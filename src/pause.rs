@@ -0,0 +1,99 @@
+use cosmwasm_std::{Addr, Order, StdResult, Storage};
+
+use crate::{
+    msg::{PauseScope, PauseStatusResponse},
+    state::{PauseState, GUARDIAN, PAUSE, PAUSED_CHANNELS},
+    ContractError,
+};
+
+fn load(storage: &dyn Storage) -> StdResult<PauseState> {
+    Ok(PAUSE.may_load(storage)?.unwrap_or_default())
+}
+
+/// Whether any scope is paused.
+pub fn is_anything_paused(storage: &dyn Storage) -> StdResult<bool> {
+    let state = load(storage)?;
+    Ok(state.all
+        || state.ibc_receive
+        || state.ibc_send
+        || PAUSED_CHANNELS
+            .keys(storage, None, None, Order::Ascending)
+            .next()
+            .is_some())
+}
+
+/// Fails if everything is paused.
+pub fn ensure_not_paused(storage: &dyn Storage) -> Result<(), ContractError> {
+    if load(storage)?.all {
+        return Err(ContractError::Paused {});
+    }
+    Ok(())
+}
+
+fn ensure_channel_not_paused(storage: &dyn Storage, channel: &str) -> Result<(), ContractError> {
+    if PAUSED_CHANNELS.has(storage, channel.to_string()) {
+        return Err(ContractError::ChannelPaused {
+            channel: channel.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Fails if packets can't be sent over `channel`.
+pub fn ensure_send_not_paused(storage: &dyn Storage, channel: &str) -> Result<(), ContractError> {
+    ensure_not_paused(storage)?;
+    if load(storage)?.ibc_send {
+        return Err(ContractError::IbcSendPaused {});
+    }
+    ensure_channel_not_paused(storage, channel)
+}
+
+/// Fails if packets received over `channel` can't be processed.
+pub fn ensure_receive_not_paused(
+    storage: &dyn Storage,
+    channel: &str,
+) -> Result<(), ContractError> {
+    ensure_not_paused(storage)?;
+    if load(storage)?.ibc_receive {
+        return Err(ContractError::IbcReceivePaused {});
+    }
+    ensure_channel_not_paused(storage, channel)
+}
+
+/// Pauses or resumes `scope`.
+pub fn set_paused(storage: &mut dyn Storage, scope: &PauseScope, paused: bool) -> StdResult<()> {
+    let mut state = load(storage)?;
+    match scope {
+        PauseScope::All => state.all = paused,
+        PauseScope::IbcReceive => state.ibc_receive = paused,
+        PauseScope::IbcSend => state.ibc_send = paused,
+        PauseScope::Channel { channel } => {
+            if paused {
+                PAUSED_CHANNELS.save(storage, channel.clone(), &true)?;
+            } else {
+                PAUSED_CHANNELS.remove(storage, channel.clone());
+            }
+            return Ok(());
+        }
+    }
+    PAUSE.save(storage, &state)
+}
+
+/// Whether `sender` is the guardian.
+pub fn is_guardian(storage: &dyn Storage, sender: &Addr) -> StdResult<bool> {
+    Ok(GUARDIAN.may_load(storage)?.as_ref() == Some(sender))
+}
+
+pub fn query_pause_status(storage: &dyn Storage) -> StdResult<PauseStatusResponse> {
+    let state = load(storage)?;
+    let channels = PAUSED_CHANNELS
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    Ok(PauseStatusResponse {
+        all: state.all,
+        ibc_receive: state.ibc_receive,
+        ibc_send: state.ibc_send,
+        channels,
+        guardian: GUARDIAN.may_load(storage)?.map(String::from),
+    })
+}
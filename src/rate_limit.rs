@@ -0,0 +1,239 @@
+use cosmwasm_std::{Env, StdError, StdResult, Storage, Timestamp, Uint128};
+
+use crate::{
+    msg::{FlowUsage, RateLimitUsageResponse},
+    state::{FlowBucket, RateLimit, RATE_LIMITS, RATE_LIMIT_BUCKETS},
+    ContractError,
+};
+
+/// Number of buckets a window is tracked in. A bucket keeps counting until it
+/// lies entirely outside the window, so the window rolls forward one bucket
+/// at a time and tokens count for up to a bucket longer than the window.
+const BUCKETS: u64 = 10;
+
+/// Direction tokens move in over a channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Flow {
+    In,
+    Out,
+}
+
+impl Flow {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Flow::In => "inflow",
+            Flow::Out => "outflow",
+        }
+    }
+}
+
+fn limit_of(storage: &dyn Storage, channel: &str, flow: Flow) -> StdResult<Option<RateLimit>> {
+    let limits = RATE_LIMITS
+        .may_load(storage, channel.to_string())?
+        .unwrap_or_default();
+    Ok(match flow {
+        Flow::In => limits.inflow,
+        Flow::Out => limits.outflow,
+    })
+}
+
+// `u64::div_ceil` is newer than the toolchain CI builds with
+#[allow(unknown_lints, clippy::manual_div_ceil)]
+fn bucket_seconds(limit: &RateLimit) -> u64 {
+    ((limit.window_seconds + BUCKETS - 1) / BUCKETS).max(1)
+}
+
+/// Start of the bucket `now` falls into.
+fn bucket_start(limit: &RateLimit, now: Timestamp) -> Timestamp {
+    let size = bucket_seconds(limit);
+    Timestamp::from_seconds(now.seconds() / size * size)
+}
+
+/// Buckets of `channel` that still count against `limit` at `now`, which are
+/// the ones overlapping the window ending now.
+fn live_buckets(
+    storage: &dyn Storage,
+    channel: &str,
+    flow: Flow,
+    limit: &RateLimit,
+    now: Timestamp,
+) -> StdResult<Vec<FlowBucket>> {
+    let oldest = bucket_start(limit, now)
+        .seconds()
+        .saturating_sub(BUCKETS * bucket_seconds(limit));
+    let buckets = RATE_LIMIT_BUCKETS
+        .may_load(storage, (channel.to_string(), flow.as_str().to_string()))?
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|bucket| bucket.start.seconds() >= oldest)
+        .collect();
+    Ok(buckets)
+}
+
+fn used(buckets: &[FlowBucket]) -> Uint128 {
+    buckets.iter().map(|bucket| bucket.amount).sum()
+}
+
+/// Fails if moving `amount` over `channel` would go over its rate limit for
+/// `flow`. Nothing is counted until `record` is called, so packets can be
+/// checked before they are processed and only counted once they succeed.
+pub fn check(
+    storage: &dyn Storage,
+    env: &Env,
+    channel: &str,
+    flow: Flow,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    let limit = match limit_of(storage, channel, flow)? {
+        Some(limit) => limit,
+        None => return Ok(()),
+    };
+    let buckets = live_buckets(storage, channel, flow, &limit, env.block.time)?;
+    let total = used(&buckets)
+        .checked_add(amount)
+        .map_err(StdError::overflow)?;
+    if total > limit.max_amount {
+        return Err(ContractError::RateLimitExceeded {
+            channel: channel.to_string(),
+            flow: flow.as_str().to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Counts `amount` moved over `channel` against its rate limit for `flow`.
+/// Channels without a limit aren't tracked.
+pub fn record(
+    storage: &mut dyn Storage,
+    env: &Env,
+    channel: &str,
+    flow: Flow,
+    amount: Uint128,
+) -> StdResult<()> {
+    let limit = match limit_of(storage, channel, flow)? {
+        Some(limit) => limit,
+        None => return Ok(()),
+    };
+    let start = bucket_start(&limit, env.block.time);
+    let mut buckets = live_buckets(storage, channel, flow, &limit, env.block.time)?;
+    match buckets.iter_mut().find(|bucket| bucket.start == start) {
+        Some(bucket) => bucket.amount += amount,
+        None => buckets.push(FlowBucket { start, amount }),
+    }
+    RATE_LIMIT_BUCKETS.save(
+        storage,
+        (channel.to_string(), flow.as_str().to_string()),
+        &buckets,
+    )
+}
+
+/// Takes back `amount` recorded in this block, when the transfer it was
+/// recorded for is rolled back.
+pub fn unrecord(
+    storage: &mut dyn Storage,
+    env: &Env,
+    channel: &str,
+    flow: Flow,
+    amount: Uint128,
+) -> StdResult<()> {
+    release(storage, env, channel, flow, amount, Some(env.block.time))
+}
+
+/// Takes back `amount` recorded for a transfer that failed after its block,
+/// from the bucket of `recorded_at`. Nothing is given back once that bucket
+/// stopped counting. When the time isn't known the newest buckets are
+/// emptied first.
+pub fn release(
+    storage: &mut dyn Storage,
+    env: &Env,
+    channel: &str,
+    flow: Flow,
+    amount: Uint128,
+    recorded_at: Option<Timestamp>,
+) -> StdResult<()> {
+    let limit = match limit_of(storage, channel, flow)? {
+        Some(limit) => limit,
+        None => return Ok(()),
+    };
+    let mut buckets = live_buckets(storage, channel, flow, &limit, env.block.time)?;
+    match recorded_at {
+        Some(time) => {
+            let start = bucket_start(&limit, time);
+            if let Some(bucket) = buckets.iter_mut().find(|bucket| bucket.start == start) {
+                bucket.amount = bucket.amount.saturating_sub(amount);
+            }
+        }
+        None => {
+            let mut left = amount;
+            for bucket in buckets.iter_mut().rev() {
+                let taken = left.min(bucket.amount);
+                bucket.amount -= taken;
+                left -= taken;
+            }
+        }
+    }
+    RATE_LIMIT_BUCKETS.save(
+        storage,
+        (channel.to_string(), flow.as_str().to_string()),
+        &buckets,
+    )
+}
+
+/// `check` and `record` in one, for flows that revert as a whole when they
+/// fail.
+pub fn consume(
+    storage: &mut dyn Storage,
+    env: &Env,
+    channel: &str,
+    flow: Flow,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    check(storage, env, channel, flow, amount)?;
+    record(storage, env, channel, flow, amount)?;
+    Ok(())
+}
+
+fn flow_usage(
+    storage: &dyn Storage,
+    env: &Env,
+    channel: &str,
+    flow: Flow,
+    limit: Option<RateLimit>,
+) -> StdResult<Option<FlowUsage>> {
+    let limit = match limit {
+        Some(limit) => limit,
+        None => return Ok(None),
+    };
+    let buckets = live_buckets(storage, channel, flow, &limit, env.block.time)?;
+    let used = used(&buckets);
+    // a bucket stops counting once the window ending now no longer overlaps it
+    let next_release = buckets
+        .iter()
+        .filter(|bucket| !bucket.amount.is_zero())
+        .map(|bucket| {
+            bucket
+                .start
+                .plus_seconds((BUCKETS + 1) * bucket_seconds(&limit))
+        })
+        .min();
+    Ok(Some(FlowUsage {
+        available: limit.max_amount.saturating_sub(used),
+        used,
+        next_release,
+        limit,
+    }))
+}
+
+pub fn query_rate_limit_usage(
+    storage: &dyn Storage,
+    env: Env,
+    channel: String,
+) -> StdResult<RateLimitUsageResponse> {
+    let limits = RATE_LIMITS
+        .may_load(storage, channel.clone())?
+        .unwrap_or_default();
+    Ok(RateLimitUsageResponse {
+        inflow: flow_usage(storage, &env, &channel, Flow::In, limits.inflow)?,
+        outflow: flow_usage(storage, &env, &channel, Flow::Out, limits.outflow)?,
+    })
+}
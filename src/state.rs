@@ -1,14 +1,30 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Timestamp, Uint128};
 use cw_storage_plus::{Item, Map};
 
 use cw20::{AllowanceResponse, Logo, MarketingInfoResponse};
+use cw_utils::Expiration;
 
+use crate::msg::Callback;
 use crate::ContractError;
 
-// Mapping between connections and the counter on that connection.
-pub const CONNECTION_COUNTS: Map<String, u32> = Map::new("connection_counts");
-pub const IS_MAIN_CONTACT: Map<String, bool> = Map::new("is_main");
+// Nonce of the last packet sent by this contract.
+pub const PACKET_NONCE: Item<u64> = Item::new("packet_nonce");
+// Whether this is the main deployment issuing the token, satellites only
+// hold vouchers for tokens escrowed on the main deployment.
+pub const IS_MAIN: Item<bool> = Item::new("is_main");
+
+/// Counterparty a satellite accepts tokens from. For a token originating
+/// behind an ICS-20 channel this is the transfer module holding it.
+#[cw_serde]
+pub struct MainDeployment {
+    pub connection_id: String,
+    pub port_id: String,
+}
+
+// Where the main deployment lives, satellites only mint vouchers for tokens arriving
+// from it. Unset on the main deployment.
+pub const MAIN_DEPLOYMENT: Item<MainDeployment> = Item::new("main_deployment");
 #[cw_serde]
 pub struct TokenInfo {
     pub name: String,
@@ -50,12 +66,195 @@ impl Chains {
 
 pub const CHAINS: Item<Chains> = Item::new("chains");
 
-pub const TOKEN_INFO_CHAIN: Item<TokenInfo> = Item::new("token_infor_1");
-pub const TOKEN_INFO: Map<String, TokenInfo> = Map::new("token_info");
+#[cw_serde]
+pub enum ChannelStatus {
+    Open,
+    /// We asked for the channel to be closed and wait for the handshake to finish.
+    Closing,
+    Closed,
+}
+
+#[cw_serde]
+pub struct ChannelInfo {
+    /// Id of the channel on this chain.
+    pub id: String,
+    /// Port of this contract the channel is bound to.
+    pub port_id: String,
+    pub counterparty_port_id: String,
+    pub counterparty_channel_id: String,
+    pub connection_id: String,
+    /// Protocol version agreed on during the handshake.
+    pub version: String,
+    pub opened_at: Timestamp,
+    pub status: ChannelStatus,
+}
+
+// Every channel that completed the handshake, by channel id.
+pub const CHANNELS: Map<String, ChannelInfo> = Map::new("channels");
+
+/// Packet counters of a channel, kept after the channel closes.
+#[cw_serde]
+#[derive(Default)]
+pub struct ChannelStats {
+    pub packets_sent: u64,
+    /// Packets received from the counterparty, including the ones answered
+    /// with an error ack.
+    pub packets_received: u64,
+    pub acks_ok: u64,
+    pub acks_error: u64,
+    pub timeouts: u64,
+    /// Tokens sent to the counterparty, whether or not they arrived.
+    pub volume_sent: Uint128,
+    /// Tokens credited to recipients on this chain.
+    pub volume_received: Uint128,
+}
+
+// Denom the counterparty on an ICS-20 channel sends this token as, set when the token
+// originates on the other side of the channel.
+pub const REMOTE_DENOMS: Map<String, String> = Map::new("remote_denoms");
+// Allowances on the counterparty involving a local account, by channel, owner and spender,
+// updated from the results of acknowledged packets.
+pub const REMOTE_ALLOWANCES: Map<(String, String, String), AllowanceResponse> =
+    Map::new("remote_allowances");
+#[cw_serde]
+#[derive(Default)]
+pub struct PauseState {
+    pub all: bool,
+    pub ibc_receive: bool,
+    pub ibc_send: bool,
+}
+
+// What is paused, unset until something is paused for the first time.
+pub const PAUSE: Item<PauseState> = Item::new("pause");
+// Ids of the channels paused on their own.
+pub const PAUSED_CHANNELS: Map<String, bool> = Map::new("paused_channels");
+// May pause the contract besides the owner.
+pub const GUARDIAN: Item<Addr> = Item::new("guardian");
+
+/// Most tokens allowed to move in one direction over a channel during any
+/// window of `window_seconds`, rolling forward with time.
+#[cw_serde]
+pub struct RateLimit {
+    pub max_amount: Uint128,
+    pub window_seconds: u64,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct RateLimits {
+    pub inflow: Option<RateLimit>,
+    pub outflow: Option<RateLimit>,
+}
+
+/// Tokens moved during a slice of a rate limit window starting at `start`.
+#[cw_serde]
+pub struct FlowBucket {
+    pub start: Timestamp,
+    pub amount: Uint128,
+}
+
+/// Source of the control packets the main deployment applies.
+#[cw_serde]
+pub struct Controller {
+    /// Control channel on this chain, which pins the counterparty port and channel
+    /// the packets come from.
+    pub channel: String,
+    /// Sender of the packets on the counterparty chain, usually the owner of the
+    /// deployment there.
+    pub sender: String,
+}
+
+// Only source of control packets that are applied, none are while unset.
+pub const CONTROLLER: Item<Controller> = Item::new("controller");
+// Rate limits by channel id.
+pub const RATE_LIMITS: Map<String, RateLimits> = Map::new("rate_limits");
+// Tokens moved during the current window in buckets, oldest first, by channel id and flow
+// ("inflow" or "outflow").
+pub const RATE_LIMIT_BUCKETS: Map<(String, String), Vec<FlowBucket>> =
+    Map::new("rate_limit_buckets");
+// Packet statistics by channel id.
+pub const CHANNEL_STATS: Map<String, ChannelStats> = Map::new("channel_stats");
+
+/// A minter proposed by the current minter, which takes over once it accepts.
+#[cw_serde]
+pub struct PendingMinter {
+    pub minter: Addr,
+    pub expiry: Expiration,
+}
+
+// Unset unless a minter handover is in progress.
+pub const PENDING_MINTER: Item<PendingMinter> = Item::new("pending_minter");
+
+#[cw_serde]
+pub struct Ownership {
+    /// Unset once ownership is renounced.
+    pub owner: Option<Addr>,
+    /// Proposed by the owner, becomes the owner once it accepts.
+    pub pending_owner: Option<Addr>,
+}
+
+// Owner of the contract, allowed to manage channels, chains, limits and pausing.
+pub const OWNERSHIP: Item<Ownership> = Item::new("ownership");
+
+/// An outbound transfer whose tokens are escrowed until the counterparty
+/// acknowledges it.
+#[cw_serde]
+pub struct InFlightPacket {
+    pub sender: Addr,
+    pub amount: Uint128,
+    pub channel: String,
+    /// Told how the transfer ended.
+    pub callback: Option<Callback>,
+    /// Block time the transfer was sent at, unknown for ICS-20 transfers.
+    pub sent_at: Option<Timestamp>,
+}
+
+/// Tokens moved to `contract` by a `Send` or `SendFrom` packet while its
+/// receive hook runs.
+#[cw_serde]
+pub struct PendingSend {
+    /// Account the tokens came from, the owner for `SendFrom`.
+    pub sender: Addr,
+    pub contract: Addr,
+    pub amount: Uint128,
+    /// Account whose allowance paid for a `SendFrom`.
+    pub spender: Option<Addr>,
+}
+
+/// Tokens credited by a `Transfer` packet while its callback runs.
+#[cw_serde]
+pub struct PendingTransfer {
+    pub channel: String,
+    pub recipient: Addr,
+    pub amount: Uint128,
+    /// Part of the amount that was taken from `CHANNEL_OUTSTANDING`.
+    pub outstanding: Uint128,
+}
+
+// Transfer received over IBC whose callback is being executed, undone if the callback fails.
+// Scratch state only read by the reply in the same transaction, it is never cleared and the
+// next transfer with a callback overwrites it.
+pub const PENDING_TRANSFER: Item<PendingTransfer> = Item::new("pending_transfer");
+// Send received over IBC whose receive hook is being executed, moved back if the hook fails.
+// Scratch state like `PENDING_TRANSFER`.
+pub const PENDING_SEND: Item<PendingSend> = Item::new("pending_send");
+// Outbound transfers awaiting an ack or timeout, keyed by packet nonce.
+pub const IN_FLIGHT: Map<u64, InFlightPacket> = Map::new("in_flight");
+
+pub const TOKEN_INFO: Item<TokenInfo> = Item::new("token_info");
 pub const MARKETING_INFO: Item<MarketingInfoResponse> = Item::new("marketing_info");
 pub const LOGO: Item<Logo> = Item::new("logo");
-pub const BALANCES: Map<(String, &Addr), Uint128> = Map::new("balance");
-pub const ALLOWANCES: Map<(String, &Addr, &Addr), AllowanceResponse> = Map::new("allowance");
+pub const BALANCES: Map<&Addr, Uint128> = Map::new("balance");
+// Local accounts acting for remote senders, by channel, mapped to the remote sender.
+pub const REMOTE_ACCOUNTS: Map<(String, &Addr), String> = Map::new("remote_accounts");
+// Tokens held by the main deployment for transfers sent out over a channel.
+pub const CHANNEL_ESCROW: Map<String, Uint128> = Map::new("channel_escrow");
+// Vouchers minted by a satellite deployment for tokens received over a channel.
+pub const VOUCHER_SUPPLY: Map<String, Uint128> = Map::new("voucher_supply");
+// Tokens sent to the counterparty on a channel and not sent back yet, counted when they are
+// sent and taken back if they are refunded. Includes packets still in flight.
+pub const CHANNEL_OUTSTANDING: Map<String, Uint128> = Map::new("channel_outstanding");
+pub const ALLOWANCES: Map<(&Addr, &Addr), AllowanceResponse> = Map::new("allowance");
 // TODO: After https://github.com/CosmWasm/cw-plus/issues/670 is implemented, replace this with a `MultiIndex` over `ALLOWANCES`
-pub const ALLOWANCES_SPENDER: Map<(String, &Addr, &Addr), AllowanceResponse> =
+pub const ALLOWANCES_SPENDER: Map<(&Addr, &Addr), AllowanceResponse> =
     Map::new("allowance_spender");
@@ -0,0 +1,417 @@
+use cosmwasm_std::testing::{
+    mock_env, mock_ibc_channel, mock_ibc_packet_ack, mock_ibc_packet_timeout, mock_info,
+};
+use cosmwasm_std::{
+    from_binary, CosmosMsg, IbcChannelConnectMsg, IbcMsg, IbcOrder, IbcTimeout, Uint128,
+};
+use cw20::Cw20Coin;
+
+use super::helpers::*;
+use crate::contract::{
+    execute, instantiate, query, query_balance, query_minter, query_supply_breakdown,
+    query_token_info, DEFAULT_TIMEOUT_SECONDS,
+};
+use crate::ibc::{ibc_channel_connect, ibc_packet_ack, ibc_packet_timeout, IBC_VERSION};
+use crate::msg::{
+    ChannelAmount, ExecuteMsg, IbcExecuteMsg, InstantiateMsg, PacketEnvelope, QueryMsg,
+};
+use crate::state::{MainDeployment, CHANNEL_ESCROW, CHANNEL_OUTSTANDING, VOUCHER_SUPPLY};
+use crate::{ContractError, ErrorCode};
+
+#[test]
+fn transfer_remote_escrows_and_sends_packet() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    let res = transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 40).unwrap();
+    assert_eq!(balance(&deps, "alice"), 60);
+    assert_eq!(
+        CHANNEL_ESCROW
+            .load(&deps.storage, DATA_CHANNEL.to_string())
+            .unwrap(),
+        Uint128::new(40)
+    );
+
+    assert_eq!(res.messages.len(), 1);
+    match &res.messages[0].msg {
+        CosmosMsg::Ibc(IbcMsg::SendPacket {
+            channel_id,
+            data,
+            timeout,
+        }) => {
+            assert_eq!(channel_id, DATA_CHANNEL);
+            let envelope: PacketEnvelope = from_binary(data).unwrap();
+            assert_eq!(envelope.sender, "alice");
+            assert_eq!(envelope.nonce, 1);
+            assert_eq!(
+                envelope.msg,
+                IbcExecuteMsg::Transfer {
+                    receipient: "bob".to_string(),
+                    amount: Uint128::new(40),
+                    memo: None,
+                    callback: None,
+                }
+            );
+            let expected = mock_env().block.time.plus_seconds(DEFAULT_TIMEOUT_SECONDS);
+            assert_eq!(*timeout, IbcTimeout::with_timestamp(expected));
+        }
+        msg => panic!("unexpected message {:?}", msg),
+    }
+}
+
+#[test]
+fn transfer_remote_needs_a_channel_and_tokens() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    let err = transfer_remote(deps.as_mut(), "alice", "channel-9", 40).unwrap_err();
+    assert!(matches!(err, ContractError::UnknownChannel { .. }));
+    let err = transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 0).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidZeroAmount {}));
+    transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 101).unwrap_err();
+    assert_eq!(balance(&deps, "alice"), 100);
+}
+
+#[test]
+fn main_deployment_only_releases_escrowed_tokens() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    let res = receive(
+        deps.as_mut(),
+        DATA_CHANNEL,
+        "remote",
+        transfer_packet("bob", 10),
+    );
+    assert!(parse_ack(&res.acknowledgement).is_err());
+    assert_eq!(balance(&deps, "bob"), 0);
+
+    transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 40).unwrap();
+    let res = receive(
+        deps.as_mut(),
+        DATA_CHANNEL,
+        "remote",
+        transfer_packet("bob", 10),
+    );
+    parse_ack(&res.acknowledgement).unwrap();
+    assert_eq!(balance(&deps, "bob"), 10);
+    assert_eq!(load_or_zero(&deps, CHANNEL_ESCROW, DATA_CHANNEL), 30);
+    // escrowed tokens are still part of the supply
+    assert_eq!(supply(&deps), 100);
+}
+
+#[test]
+fn satellite_mints_and_burns_vouchers() {
+    let mut deps = setup(false, &[]);
+    let res = receive(
+        deps.as_mut(),
+        DATA_CHANNEL,
+        "remote",
+        transfer_packet("alice", 50),
+    );
+    parse_ack(&res.acknowledgement).unwrap();
+    assert_eq!(balance(&deps, "alice"), 50);
+    assert_eq!(supply(&deps), 50);
+    assert_eq!(load_or_zero(&deps, VOUCHER_SUPPLY, DATA_CHANNEL), 50);
+
+    transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 20).unwrap();
+    assert_eq!(balance(&deps, "alice"), 30);
+    assert_eq!(supply(&deps), 30);
+    assert_eq!(load_or_zero(&deps, VOUCHER_SUPPLY, DATA_CHANNEL), 30);
+    assert_eq!(load_or_zero(&deps, CHANNEL_ESCROW, DATA_CHANNEL), 0);
+}
+
+#[test]
+fn satellite_issues_no_tokens() {
+    let mut deps = mock_deps();
+    let msg = InstantiateMsg {
+        name: "Cross Chain Token".to_string(),
+        symbol: "CCT".to_string(),
+        decimals: 6,
+        initial_balances: vec![Cw20Coin {
+            address: "alice".to_string(),
+            amount: Uint128::new(100),
+        }],
+        ..Default::default()
+    };
+    let err = instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::NotMainDeployment {}));
+
+    let mut deps = setup(false, &[]);
+    let msg = ExecuteMsg::Mint {
+        recipient: "alice".to_string(),
+        amount: Uint128::new(100),
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::NotMainDeployment {}));
+}
+
+#[test]
+fn supply_breakdown_follows_tokens_across_the_channel() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    let res = transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 40).unwrap();
+    let amounts = |amount: u128| {
+        vec![ChannelAmount {
+            channel: DATA_CHANNEL.to_string(),
+            amount: Uint128::new(amount),
+        }]
+    };
+
+    let breakdown = query_supply_breakdown(deps.as_ref()).unwrap();
+    assert!(breakdown.is_main);
+    assert_eq!(breakdown.local_supply, Uint128::new(60));
+    assert_eq!(breakdown.escrowed, amounts(40));
+    assert_eq!(breakdown.in_flight, amounts(40));
+    assert_eq!(breakdown.outstanding, amounts(40));
+    assert_eq!(breakdown.vouchers, vec![]);
+
+    let msg = mock_ibc_packet_ack(DATA_CHANNEL, &sent_packet(&res), transfer_ack(40)).unwrap();
+    ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+    let breakdown = query_supply_breakdown(deps.as_ref()).unwrap();
+    assert_eq!(breakdown.in_flight, vec![]);
+    assert_eq!(breakdown.outstanding, amounts(40));
+
+    receive(
+        deps.as_mut(),
+        DATA_CHANNEL,
+        "remote",
+        transfer_packet("bob", 15),
+    );
+    let breakdown = query_supply_breakdown(deps.as_ref()).unwrap();
+    assert_eq!(breakdown.local_supply, Uint128::new(75));
+    assert_eq!(breakdown.escrowed, amounts(25));
+    assert_eq!(breakdown.outstanding, amounts(25));
+}
+
+#[test]
+fn satellite_vouchers_are_not_burned() {
+    let mut deps = setup(false, &[]);
+    receive(
+        deps.as_mut(),
+        DATA_CHANNEL,
+        "remote",
+        transfer_packet("alice", 50),
+    );
+    let msg = ExecuteMsg::IncreaseAllowance {
+        spender: "bob".to_string(),
+        amount: Uint128::new(50),
+        expires: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::Burn {
+        amount: Uint128::new(20),
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::VoucherBurn {}));
+    let msg = ExecuteMsg::BurnFrom {
+        owner: "alice".to_string(),
+        amount: Uint128::new(20),
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::VoucherBurn {}));
+    let res = receive(
+        deps.as_mut(),
+        DATA_CHANNEL,
+        "alice",
+        IbcExecuteMsg::Burn {
+            amount: Uint128::new(20),
+        },
+    );
+    let err = parse_ack(&res.acknowledgement).unwrap_err();
+    assert_eq!(err.code, ErrorCode::VoucherBurn);
+
+    // every voucher is still backed by the channel it came over
+    let breakdown = query_supply_breakdown(deps.as_ref()).unwrap();
+    assert_eq!(breakdown.local_supply, Uint128::new(50));
+    assert_eq!(
+        breakdown.vouchers,
+        vec![ChannelAmount {
+            channel: DATA_CHANNEL.to_string(),
+            amount: Uint128::new(50),
+        }]
+    );
+}
+
+#[test]
+fn tokens_can_return_before_their_ack() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    let res = transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 40).unwrap();
+    let amounts = |amount: u128| {
+        vec![ChannelAmount {
+            channel: DATA_CHANNEL.to_string(),
+            amount: Uint128::new(amount),
+        }]
+    };
+
+    // the counterparty credited bob, who sends some back before the ack is relayed
+    receive(
+        deps.as_mut(),
+        DATA_CHANNEL,
+        "remote",
+        transfer_packet("bob", 15),
+    );
+    let breakdown = query_supply_breakdown(deps.as_ref()).unwrap();
+    assert_eq!(breakdown.escrowed, amounts(25));
+    assert_eq!(breakdown.in_flight, amounts(40));
+    assert_eq!(breakdown.outstanding, amounts(25));
+
+    let msg = mock_ibc_packet_ack(DATA_CHANNEL, &sent_packet(&res), transfer_ack(40)).unwrap();
+    ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+    let breakdown = query_supply_breakdown(deps.as_ref()).unwrap();
+    assert_eq!(breakdown.local_supply, Uint128::new(75));
+    assert_eq!(breakdown.escrowed, amounts(25));
+    assert_eq!(breakdown.in_flight, vec![]);
+    assert_eq!(breakdown.outstanding, amounts(25));
+}
+
+#[test]
+fn refunded_tokens_are_no_longer_outstanding() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    let res = transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 40).unwrap();
+    transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 10).unwrap();
+    assert_eq!(load_or_zero(&deps, CHANNEL_OUTSTANDING, DATA_CHANNEL), 50);
+
+    let msg = mock_ibc_packet_timeout(DATA_CHANNEL, &sent_packet(&res)).unwrap();
+    ibc_packet_timeout(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(load_or_zero(&deps, CHANNEL_OUTSTANDING, DATA_CHANNEL), 10);
+    assert_eq!(load_or_zero(&deps, CHANNEL_ESCROW, DATA_CHANNEL), 10);
+}
+
+#[test]
+fn token_info_is_shared_by_all_channels() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    let info = query_token_info(deps.as_ref()).unwrap();
+    assert_eq!(info.symbol, "CCT");
+    assert_eq!(info.total_supply, Uint128::new(100));
+
+    let msg = ExecuteMsg::Burn {
+        amount: Uint128::new(30),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+    let info = query_token_info(deps.as_ref()).unwrap();
+    assert_eq!(info.total_supply, Uint128::new(70));
+    assert_eq!(query_minter(deps.as_ref()).unwrap(), None);
+}
+
+#[test]
+fn local_and_remote_transfers_share_one_ledger() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    let msg = ExecuteMsg::Transfer {
+        recipient: "bob".to_string(),
+        amount: Uint128::new(30),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+    assert_eq!(
+        query_balance(deps.as_ref(), "bob".to_string())
+            .unwrap()
+            .balance,
+        Uint128::new(30)
+    );
+
+    // bob can send away what was received locally, and tokens coming back land
+    // on the same balance
+    transfer_remote(deps.as_mut(), "bob", DATA_CHANNEL, 20).unwrap();
+    receive(
+        deps.as_mut(),
+        DATA_CHANNEL,
+        "remote",
+        transfer_packet("bob", 5),
+    );
+    assert_eq!(balance(&deps, "bob"), 15);
+
+    let msg = ExecuteMsg::IncreaseAllowance {
+        spender: "carol".to_string(),
+        amount: Uint128::new(10),
+        expires: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap();
+    let msg = ExecuteMsg::TransferFrom {
+        owner: "bob".to_string(),
+        recipient: "carol".to_string(),
+        amount: Uint128::new(10),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("carol", &[]), msg).unwrap();
+    assert_eq!(balance(&deps, "bob"), 5);
+    assert_eq!(balance(&deps, "carol"), 10);
+}
+
+#[test]
+fn satellite_only_accepts_tokens_from_the_main_deployment() {
+    let mut deps = setup(false, &[]);
+    let mut channel = mock_ibc_channel("channel-3", IbcOrder::Unordered, IBC_VERSION);
+    channel.connection_id = "connection-9".to_string();
+    let msg = IbcChannelConnectMsg::new_ack(channel, IBC_VERSION);
+    ibc_channel_connect(deps.as_mut(), mock_env(), msg).unwrap();
+
+    let res = receive(
+        deps.as_mut(),
+        "channel-3",
+        "remote",
+        transfer_packet("alice", 50),
+    );
+    let err = parse_ack(&res.acknowledgement).unwrap_err();
+    assert_eq!(err.code, ErrorCode::NotMainChannel);
+    assert_eq!(balance(&deps, "alice"), 0);
+    assert_eq!(supply(&deps), 0);
+    assert_eq!(load_or_zero(&deps, VOUCHER_SUPPLY, "channel-3"), 0);
+
+    // the owner moves the main deployment behind channel-3
+    let main_deployment = MainDeployment {
+        connection_id: "connection-9".to_string(),
+        port_id: "their_port".to_string(),
+    };
+    let msg = ExecuteMsg::SetMainDeployment {
+        main_deployment: main_deployment.clone(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    let main: Option<MainDeployment> =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::MainDeployment {}).unwrap())
+            .unwrap();
+    assert_eq!(main, Some(main_deployment));
+
+    let res = receive(
+        deps.as_mut(),
+        "channel-3",
+        "remote",
+        transfer_packet("alice", 50),
+    );
+    parse_ack(&res.acknowledgement).unwrap();
+    let res = receive(
+        deps.as_mut(),
+        DATA_CHANNEL,
+        "remote",
+        transfer_packet("alice", 50),
+    );
+    let err = parse_ack(&res.acknowledgement).unwrap_err();
+    assert_eq!(err.code, ErrorCode::NotMainChannel);
+    assert_eq!(balance(&deps, "alice"), 50);
+}
+
+#[test]
+fn main_deployment_has_no_main_deployment() {
+    let main_deployment = MainDeployment {
+        connection_id: "connection-2".to_string(),
+        port_id: "their_port".to_string(),
+    };
+    let mut deps = setup(true, &[]);
+    let msg = ExecuteMsg::SetMainDeployment {
+        main_deployment: main_deployment.clone(),
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::NotSatellite {}));
+
+    let mut deps = mock_deps();
+    let msg = InstantiateMsg {
+        name: "Cross Chain Token".to_string(),
+        symbol: "CCT".to_string(),
+        decimals: 6,
+        is_main: true,
+        main_deployment: Some(main_deployment),
+        ..Default::default()
+    };
+    let err = instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::NotSatellite {}));
+}
@@ -0,0 +1,225 @@
+use cosmwasm_std::testing::{mock_env, mock_ibc_channel, mock_ibc_channel_connect_ack, mock_info};
+use cosmwasm_std::{Addr, DepsMut, IbcChannelOpenMsg, IbcOrder, Response, StdResult, Uint128};
+
+use super::helpers::*;
+use crate::contract::execute;
+use crate::ibc::{ibc_channel_connect, ibc_channel_open, CONTROL_VERSION, IBC_VERSION};
+use crate::msg::{ExecuteMsg, IbcExecuteMsg};
+use crate::state::{Controller, MinterData, TOKEN_INFO};
+use crate::{ContractError, ErrorCode};
+
+const CONTROL_CHANNEL: &str = "channel-1";
+
+fn connect_control(deps: DepsMut, channel: &str) {
+    let msg = mock_ibc_channel_connect_ack(channel, IbcOrder::Ordered, CONTROL_VERSION);
+    ibc_channel_connect(deps, mock_env(), msg).unwrap();
+}
+
+fn set_controller(deps: DepsMut, channel: &str, sender: &str) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::SetController {
+        controller: Some(Controller {
+            channel: channel.to_string(),
+            sender: sender.to_string(),
+        }),
+    };
+    execute(deps, mock_env(), mock_info("creator", &[]), msg)
+}
+
+/// Main deployment minted by "minter", trusting control packets from "gov"
+/// over `CONTROL_CHANNEL`.
+fn setup_control() -> TestDeps {
+    let mut deps = setup(true, &[("alice", 100)]);
+    TOKEN_INFO
+        .update(&mut deps.storage, |mut info| -> StdResult<_> {
+            info.mint = Some(MinterData {
+                minter: Addr::unchecked("minter"),
+                cap: None,
+            });
+            Ok(info)
+        })
+        .unwrap();
+    connect_control(deps.as_mut(), CONTROL_CHANNEL);
+    set_controller(deps.as_mut(), CONTROL_CHANNEL, "gov").unwrap();
+    deps
+}
+
+fn update_cap(cap: u128) -> IbcExecuteMsg {
+    IbcExecuteMsg::UpdateCap {
+        cap: Some(Uint128::new(cap)),
+    }
+}
+
+fn minter(deps: &TestDeps) -> Option<MinterData> {
+    TOKEN_INFO.load(&deps.storage).unwrap().mint
+}
+
+#[test]
+fn control_channels_are_ordered() {
+    let mut deps = setup(true, &[]);
+
+    // nothing proposed on an ordered channel, we offer the control version
+    let channel = mock_ibc_channel(CONTROL_CHANNEL, IbcOrder::Ordered, "");
+    let res = ibc_channel_open(
+        deps.as_mut(),
+        mock_env(),
+        IbcChannelOpenMsg::new_init(channel),
+    )
+    .unwrap();
+    assert_eq!(res.unwrap().version, CONTROL_VERSION);
+
+    let channel = mock_ibc_channel(CONTROL_CHANNEL, IbcOrder::Unordered, CONTROL_VERSION);
+    let err = ibc_channel_open(
+        deps.as_mut(),
+        mock_env(),
+        IbcChannelOpenMsg::new_init(channel),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::UnorderedControlChannel {}));
+
+    let channel = mock_ibc_channel(CONTROL_CHANNEL, IbcOrder::Ordered, "");
+    let msg = IbcChannelOpenMsg::new_try(channel, IBC_VERSION);
+    let err = ibc_channel_open(deps.as_mut(), mock_env(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::OrderedChannel {}));
+}
+
+#[test]
+fn controller_updates_the_cap() {
+    let mut deps = setup_control();
+    let res = receive(deps.as_mut(), CONTROL_CHANNEL, "gov", update_cap(1000));
+    parse_ack(&res.acknowledgement).unwrap();
+    assert_eq!(minter(&deps).unwrap().cap, Some(Uint128::new(1000)));
+
+    // the supply is already above the cap
+    let res = receive(deps.as_mut(), CONTROL_CHANNEL, "gov", update_cap(50));
+    let err = parse_ack(&res.acknowledgement).unwrap_err();
+    assert_eq!(err.code, ErrorCode::CannotExceedCap);
+    assert_eq!(minter(&deps).unwrap().cap, Some(Uint128::new(1000)));
+}
+
+#[test]
+fn controller_only_proposes_minters() {
+    let mut deps = setup_control();
+    let msg = IbcExecuteMsg::ProposeMinter {
+        new_minter: "next".to_string(),
+        expiry: None,
+    };
+    let res = receive(deps.as_mut(), CONTROL_CHANNEL, "gov", msg);
+    parse_ack(&res.acknowledgement).unwrap();
+    assert_eq!(minter(&deps).unwrap().minter, "minter");
+
+    let msg = ExecuteMsg::AcceptMinter {};
+    execute(deps.as_mut(), mock_env(), mock_info("next", &[]), msg).unwrap();
+    assert_eq!(minter(&deps).unwrap().minter, "next");
+
+    // a renounced minter stays renounced
+    let renounce = ExecuteMsg::RenounceMinter {};
+    execute(deps.as_mut(), mock_env(), mock_info("next", &[]), renounce).unwrap();
+    let msg = IbcExecuteMsg::ProposeMinter {
+        new_minter: "gov".to_string(),
+        expiry: None,
+    };
+    let res = receive(deps.as_mut(), CONTROL_CHANNEL, "gov", msg);
+    let err = parse_ack(&res.acknowledgement).unwrap_err();
+    assert_eq!(err.code, ErrorCode::Unauthorized);
+    assert_eq!(minter(&deps), None);
+}
+
+#[test]
+fn packets_travel_on_their_own_channels() {
+    let mut deps = setup_control();
+    let msg = IbcExecuteMsg::UpdateCap { cap: None };
+    let res = receive(deps.as_mut(), DATA_CHANNEL, "gov", msg);
+    let err = parse_ack(&res.acknowledgement).unwrap_err();
+    assert_eq!(err.code, ErrorCode::WrongChannelType);
+
+    let res = receive(
+        deps.as_mut(),
+        CONTROL_CHANNEL,
+        "alice",
+        transfer_packet("bob", 10),
+    );
+    let err = parse_ack(&res.acknowledgement).unwrap_err();
+    assert_eq!(err.code, ErrorCode::WrongChannelType);
+
+    let err = transfer_remote(deps.as_mut(), "alice", CONTROL_CHANNEL, 10).unwrap_err();
+    assert!(matches!(err, ContractError::WrongChannelType { .. }));
+    let msg = ExecuteMsg::UpdateRemoteCap {
+        channel: DATA_CHANNEL.to_string(),
+        cap: None,
+        timeout: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::WrongChannelType { .. }));
+}
+
+#[test]
+fn only_the_admin_sends_control_packets() {
+    let mut deps = setup_control();
+    let msg = ExecuteMsg::ProposeRemoteMinter {
+        channel: CONTROL_CHANNEL.to_string(),
+        new_minter: "next".to_string(),
+        expiry: None,
+        timeout: None,
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    assert_eq!(
+        sent_packet(&res).msg,
+        IbcExecuteMsg::ProposeMinter {
+            new_minter: "next".to_string(),
+            expiry: None,
+        }
+    );
+}
+
+#[test]
+fn control_packets_only_come_from_the_controller() {
+    let mut deps = setup(true, &[]);
+    connect_control(deps.as_mut(), CONTROL_CHANNEL);
+    connect_control(deps.as_mut(), "channel-2");
+
+    // nobody is trusted until a controller is set
+    let res = receive(deps.as_mut(), CONTROL_CHANNEL, "gov", update_cap(1000));
+    let err = parse_ack(&res.acknowledgement).unwrap_err();
+    assert_eq!(err.code, ErrorCode::UntrustedController);
+
+    let err = set_controller(deps.as_mut(), DATA_CHANNEL, "gov").unwrap_err();
+    assert!(matches!(err, ContractError::WrongChannelType { .. }));
+    let msg = ExecuteMsg::SetController {
+        controller: Some(Controller {
+            channel: CONTROL_CHANNEL.to_string(),
+            sender: "gov".to_string(),
+        }),
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    set_controller(deps.as_mut(), CONTROL_CHANNEL, "gov").unwrap();
+
+    let res = receive(deps.as_mut(), CONTROL_CHANNEL, "mallory", update_cap(1000));
+    let err = parse_ack(&res.acknowledgement).unwrap_err();
+    assert_eq!(err.code, ErrorCode::UntrustedController);
+    let res = receive(deps.as_mut(), "channel-2", "gov", update_cap(1000));
+    let err = parse_ack(&res.acknowledgement).unwrap_err();
+    assert_eq!(err.code, ErrorCode::UntrustedController);
+    assert_eq!(minter(&deps), None);
+}
+
+#[test]
+fn satellites_reject_control_packets() {
+    let mut deps = setup(false, &[]);
+    connect_control(deps.as_mut(), CONTROL_CHANNEL);
+    let err = set_controller(deps.as_mut(), CONTROL_CHANNEL, "gov").unwrap_err();
+    assert!(matches!(err, ContractError::NotMainDeployment {}));
+
+    let res = receive(deps.as_mut(), CONTROL_CHANNEL, "gov", update_cap(1000));
+    let err = parse_ack(&res.acknowledgement).unwrap_err();
+    assert_eq!(err.code, ErrorCode::NotMainDeployment);
+}
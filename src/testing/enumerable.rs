@@ -0,0 +1,81 @@
+use cosmwasm_std::Uint128;
+
+use super::helpers::*;
+use crate::enumerable::{query_all_accounts, query_all_balances};
+use crate::ibc::remote_account;
+use crate::msg::{AccountBalance, IbcExecuteMsg};
+
+fn touch(deps: &mut TestDeps, channel: &str, sender: &str) {
+    let msg = IbcExecuteMsg::IncreaseAllowance {
+        spender: "spender".to_string(),
+        amount: Uint128::new(1),
+        expires: None,
+    };
+    receive(deps.as_mut(), channel, sender, msg);
+}
+
+#[test]
+fn all_balances_paginates_the_ledger() {
+    let deps = setup(true, &[("carol", 10), ("alice", 100), ("bob", 50)]);
+    let entry = |address: &str, balance: u128| AccountBalance {
+        address: address.to_string(),
+        balance: Uint128::new(balance),
+        remote_sender: None,
+    };
+
+    let page = query_all_balances(deps.as_ref(), None, None, Some(2)).unwrap();
+    assert_eq!(page.balances, vec![entry("alice", 100), entry("bob", 50)]);
+    let page = query_all_balances(deps.as_ref(), None, Some("bob".to_string()), Some(2)).unwrap();
+    assert_eq!(page.balances, vec![entry("carol", 10)]);
+
+    let accounts = query_all_accounts(deps.as_ref(), Some("alice".to_string()), None).unwrap();
+    assert_eq!(accounts.accounts, vec!["bob", "carol"]);
+}
+
+#[test]
+fn all_balances_of_a_channel_lists_its_remote_accounts() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    connect(deps.as_mut(), "channel-2");
+    touch(&mut deps, DATA_CHANNEL, "dave");
+    touch(&mut deps, DATA_CHANNEL, "erin");
+    touch(&mut deps, "channel-2", "frank");
+
+    let mut expected: Vec<_> = ["dave", "erin"]
+        .iter()
+        .map(|sender| AccountBalance {
+            address: remote_account(&deps.api, DATA_CHANNEL, sender)
+                .unwrap()
+                .into(),
+            balance: Uint128::zero(),
+            remote_sender: Some(sender.to_string()),
+        })
+        .collect();
+    expected.sort_by(|a, b| a.address.cmp(&b.address));
+
+    let channel = Some(DATA_CHANNEL.to_string());
+    let page = query_all_balances(deps.as_ref(), channel.clone(), None, None).unwrap();
+    assert_eq!(page.balances, expected);
+
+    let start_after = Some(expected[0].address.clone());
+    let page = query_all_balances(deps.as_ref(), channel, start_after, Some(10)).unwrap();
+    assert_eq!(page.balances, expected[1..]);
+
+    let page =
+        query_all_balances(deps.as_ref(), Some("channel-2".to_string()), None, None).unwrap();
+    assert_eq!(page.balances.len(), 1);
+    assert_eq!(page.balances[0].remote_sender.as_deref(), Some("frank"));
+}
+
+#[test]
+fn failed_packets_list_no_remote_account() {
+    let mut deps = setup(true, &[]);
+    let msg = IbcExecuteMsg::Burn {
+        amount: Uint128::new(5),
+    };
+    let res = receive(deps.as_mut(), DATA_CHANNEL, "dave", msg);
+    parse_ack(&res.acknowledgement).unwrap_err();
+
+    let channel = Some(DATA_CHANNEL.to_string());
+    let page = query_all_balances(deps.as_ref(), channel, None, None).unwrap();
+    assert_eq!(page.balances, vec![]);
+}
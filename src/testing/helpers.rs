@@ -0,0 +1,245 @@
+use std::marker::PhantomData;
+
+use cosmwasm_std::testing::{
+    mock_env, mock_ibc_channel_connect_ack, mock_ibc_packet_recv, mock_info, MockApi, MockQuerier,
+    MockStorage,
+};
+use cosmwasm_std::{
+    from_binary, Addr, Api, Binary, CanonicalAddr, CosmosMsg, DepsMut, IbcAcknowledgement, IbcMsg,
+    IbcOrder, IbcReceiveResponse, OwnedDeps, RecoverPubkeyError, Response, StdError, StdResult,
+    Uint128, VerificationError,
+};
+use cw20::Cw20Coin;
+use cw_storage_plus::Map;
+
+use crate::ack::{make_ack_success, parse_ack_error, Ack, AckError, PacketResult};
+use crate::contract::{execute, instantiate};
+use crate::ibc::{ibc_channel_connect, ibc_packet_receive, IBC_VERSION};
+use crate::msg::{ExecuteMsg, IbcExecuteMsg, InstantiateMsg, PacketEnvelope};
+use crate::state::{MainDeployment, BALANCES, TOKEN_INFO};
+use crate::ContractError;
+
+pub const DATA_CHANNEL: &str = "channel-0";
+
+/// `MockApi` only humanizes canonical addresses of its own length, remote
+/// accounts are 32 byte hashes. Those are humanized as `remote` followed by
+/// the hash in hex.
+pub struct TestApi(MockApi);
+
+const REMOTE_PREFIX: &str = "remote";
+
+impl Api for TestApi {
+    fn addr_validate(&self, human: &str) -> StdResult<Addr> {
+        let canonical = self.addr_canonicalize(human)?;
+        if self.addr_humanize(&canonical)? != human {
+            return Err(StdError::generic_err(
+                "Invalid input: address not normalized",
+            ));
+        }
+        Ok(Addr::unchecked(human))
+    }
+
+    fn addr_canonicalize(&self, human: &str) -> StdResult<CanonicalAddr> {
+        match human.strip_prefix(REMOTE_PREFIX) {
+            Some(hex) if hex.len() == 64 => (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+                .collect::<Result<Vec<_>, _>>()
+                .map(CanonicalAddr::from)
+                .map_err(|err| StdError::generic_err(err.to_string())),
+            _ => self.0.addr_canonicalize(human),
+        }
+    }
+
+    fn addr_humanize(&self, canonical: &CanonicalAddr) -> StdResult<Addr> {
+        if canonical.len() != 32 {
+            return self.0.addr_humanize(canonical);
+        }
+        let hex: String = canonical
+            .as_slice()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        Ok(Addr::unchecked(format!("{}{}", REMOTE_PREFIX, hex)))
+    }
+
+    fn secp256k1_verify(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> Result<bool, VerificationError> {
+        self.0.secp256k1_verify(message_hash, signature, public_key)
+    }
+
+    fn secp256k1_recover_pubkey(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        recovery_param: u8,
+    ) -> Result<Vec<u8>, RecoverPubkeyError> {
+        self.0
+            .secp256k1_recover_pubkey(message_hash, signature, recovery_param)
+    }
+
+    fn ed25519_verify(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> Result<bool, VerificationError> {
+        self.0.ed25519_verify(message, signature, public_key)
+    }
+
+    fn ed25519_batch_verify(
+        &self,
+        messages: &[&[u8]],
+        signatures: &[&[u8]],
+        public_keys: &[&[u8]],
+    ) -> Result<bool, VerificationError> {
+        self.0
+            .ed25519_batch_verify(messages, signatures, public_keys)
+    }
+
+    fn debug(&self, message: &str) {
+        self.0.debug(message)
+    }
+}
+
+pub type TestDeps = OwnedDeps<MockStorage, TestApi, MockQuerier>;
+
+pub fn mock_deps() -> TestDeps {
+    OwnedDeps {
+        storage: MockStorage::default(),
+        api: TestApi(MockApi::default()),
+        querier: MockQuerier::default(),
+        custom_query_type: PhantomData,
+    }
+}
+
+/// Instantiates the token with `initial_balances` and connects `DATA_CHANNEL`.
+/// Instantiates a deployment over `DATA_CHANNEL`, the main one issuing the
+/// token or a satellite.
+pub fn setup(is_main: bool, initial_balances: &[(&str, u128)]) -> TestDeps {
+    let mut deps = mock_deps();
+    let msg = InstantiateMsg {
+        name: "Cross Chain Token".to_string(),
+        symbol: "CCT".to_string(),
+        decimals: 6,
+        initial_balances: initial_balances
+            .iter()
+            .map(|(address, amount)| Cw20Coin {
+                address: address.to_string(),
+                amount: Uint128::new(*amount),
+            })
+            .collect(),
+        is_main,
+        other_chains: vec!["their_port".to_string()],
+        main_deployment: (!is_main).then(|| MainDeployment {
+            connection_id: "connection-2".to_string(),
+            port_id: "their_port".to_string(),
+        }),
+        ..Default::default()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    connect(deps.as_mut(), DATA_CHANNEL);
+    deps
+}
+
+pub fn connect(deps: DepsMut, channel: &str) {
+    let msg = mock_ibc_channel_connect_ack(channel, IbcOrder::Unordered, IBC_VERSION);
+    ibc_channel_connect(deps, mock_env(), msg).unwrap();
+}
+
+/// Sends `amount` from `sender` to bob on the counterparty of `channel`.
+pub fn transfer_remote(
+    deps: DepsMut,
+    sender: &str,
+    channel: &str,
+    amount: u128,
+) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::TransferRemote {
+        channel: channel.to_string(),
+        recipient: "bob".to_string(),
+        amount: Uint128::new(amount),
+        timeout: None,
+        memo: None,
+        callback: None,
+        ack_callback: None,
+    };
+    execute(deps, mock_env(), mock_info(sender, &[]), msg)
+}
+
+/// Decodes the envelope of the packet sent by `res`.
+pub fn sent_packet(res: &Response) -> PacketEnvelope {
+    let data = res
+        .messages
+        .iter()
+        .find_map(|sub| match &sub.msg {
+            CosmosMsg::Ibc(IbcMsg::SendPacket { data, .. }) => Some(data.clone()),
+            _ => None,
+        })
+        .expect("no packet sent");
+    from_binary(&data).unwrap()
+}
+
+pub fn transfer_packet(recipient: &str, amount: u128) -> IbcExecuteMsg {
+    IbcExecuteMsg::Transfer {
+        receipient: recipient.to_string(),
+        amount: Uint128::new(amount),
+        memo: None,
+        callback: None,
+    }
+}
+
+/// Receives `msg` sent by `sender` over `channel`.
+pub fn receive(
+    deps: DepsMut,
+    channel: &str,
+    sender: &str,
+    msg: IbcExecuteMsg,
+) -> IbcReceiveResponse {
+    let envelope = PacketEnvelope {
+        sender: sender.to_string(),
+        chain_id: "remote-chain".to_string(),
+        nonce: 1,
+        msg,
+    };
+    let packet = mock_ibc_packet_recv(channel, &envelope).unwrap();
+    ibc_packet_receive(deps, mock_env(), packet).unwrap()
+}
+
+/// Decodes an acknowledgement into its result or error.
+pub fn parse_ack(ack: &Binary) -> Result<Binary, AckError> {
+    match from_binary(ack).unwrap() {
+        Ack::Result(data) => Ok(data),
+        Ack::Error(err) => Err(parse_ack_error(&err)),
+    }
+}
+
+/// Ack of the counterparty crediting bob with a transfer.
+pub fn transfer_ack(amount: u128) -> IbcAcknowledgement {
+    IbcAcknowledgement::new(make_ack_success(&PacketResult::Transfer {
+        recipient: "bob".to_string(),
+        balance: Uint128::new(amount),
+    }))
+}
+
+pub fn balance(deps: &TestDeps, address: &str) -> u128 {
+    BALANCES
+        .may_load(&deps.storage, &Addr::unchecked(address))
+        .unwrap()
+        .unwrap_or_default()
+        .u128()
+}
+
+pub fn load_or_zero(deps: &TestDeps, map: Map<String, Uint128>, channel: &str) -> u128 {
+    map.may_load(&deps.storage, channel.to_string())
+        .unwrap()
+        .unwrap_or_default()
+        .u128()
+}
+
+pub fn supply(deps: &TestDeps) -> u128 {
+    TOKEN_INFO.load(&deps.storage).unwrap().total_supply.u128()
+}
@@ -0,0 +1,1087 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::testing::{
+    mock_env, mock_ibc_channel, mock_ibc_channel_close_confirm, mock_ibc_channel_open_try,
+    mock_ibc_packet_ack, mock_ibc_packet_timeout, mock_info, MOCK_CONTRACT_ADDR,
+};
+use cosmwasm_std::{
+    from_binary, to_binary, to_vec, Addr, Binary, CosmosMsg, DepsMut, IbcAcknowledgement,
+    IbcBasicResponse, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcMsg, IbcOrder, Reply, ReplyOn,
+    Response, SubMsg, SubMsgResponse, SubMsgResult, Uint128, WasmMsg,
+};
+use cw20::AllowanceResponse;
+use cw_utils::Expiration;
+
+use super::helpers::*;
+use crate::ack::{make_ack_fail, make_ack_success, parse_ack_error, Ack, AckError, PacketResult};
+use crate::contract::{execute, query, reply};
+use crate::ibc::{
+    ibc_channel_close, ibc_channel_connect, ibc_channel_open, ibc_packet_ack, ibc_packet_timeout,
+    remote_account, ACK_CALLBACK_REPLY_ID, IBC_VERSION, SEND_REPLY_ID, TRANSFER_CALLBACK_REPLY_ID,
+};
+use crate::msg::{
+    Callback, ExecuteMsg, IbcExecuteMsg, ListChannelsResponse, PacketEnvelope, QueryMsg,
+    ReceiveCallbackMsg, TransferCallbackMsg,
+};
+use crate::state::{
+    ChannelInfo, ChannelStats, ChannelStatus, ALLOWANCES, CHANNELS, CHANNEL_ESCROW, CHANNEL_STATS,
+    IN_FLIGHT,
+};
+use crate::{ContractError, ErrorCode};
+
+#[test]
+fn remote_accounts_are_bound_to_channel_and_sender() {
+    let deps = mock_deps();
+    let account = remote_account(&deps.api, DATA_CHANNEL, "alice").unwrap();
+    assert_eq!(
+        account,
+        remote_account(&deps.api, DATA_CHANNEL, "alice").unwrap()
+    );
+    assert_ne!(
+        account,
+        remote_account(&deps.api, "channel-1", "alice").unwrap()
+    );
+    assert_ne!(
+        account,
+        remote_account(&deps.api, DATA_CHANNEL, "bob").unwrap()
+    );
+    assert_ne!(account, "alice");
+}
+
+#[test]
+fn packets_are_executed_by_the_remote_account() {
+    let mut deps = setup(true, &[]);
+    let msg = IbcExecuteMsg::IncreaseAllowance {
+        spender: "spender".to_string(),
+        amount: Uint128::new(30),
+        expires: None,
+    };
+    let res = receive(deps.as_mut(), DATA_CHANNEL, "alice", msg);
+
+    let account = remote_account(&deps.api, DATA_CHANNEL, "alice").unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "remote_account" && attr.value == account.as_str()));
+    let allowance = ALLOWANCES
+        .load(&deps.storage, (&account, &Addr::unchecked("spender")))
+        .unwrap();
+    assert_eq!(allowance.allowance, Uint128::new(30));
+    assert_eq!(allowance.expires, Expiration::Never {});
+    // nothing is done in the name of the local address of the same name
+    assert!(!ALLOWANCES.has(
+        &deps.storage,
+        (&Addr::unchecked("alice"), &Addr::unchecked("spender"),),
+    ));
+}
+
+fn error_ack() -> IbcAcknowledgement {
+    IbcAcknowledgement::new(make_ack_fail(&ContractError::Unauthorized {}))
+}
+
+#[test]
+fn error_ack_refunds_sender() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    let res = transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 40).unwrap();
+    assert_eq!(balance(&deps, "alice"), 60);
+    assert_eq!(load_or_zero(&deps, CHANNEL_ESCROW, DATA_CHANNEL), 40);
+
+    let envelope = sent_packet(&res);
+    let msg = mock_ibc_packet_ack(DATA_CHANNEL, &envelope, error_ack()).unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(res.events[0].ty, "refund");
+    assert_eq!(balance(&deps, "alice"), 100);
+    assert_eq!(load_or_zero(&deps, CHANNEL_ESCROW, DATA_CHANNEL), 0);
+    assert!(!IN_FLIGHT.has(&deps.storage, envelope.nonce));
+}
+
+#[test]
+fn timeout_refunds_sender() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    let res = transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 40).unwrap();
+
+    let envelope = sent_packet(&res);
+    let msg = mock_ibc_packet_timeout(DATA_CHANNEL, &envelope).unwrap();
+    let res = ibc_packet_timeout(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(res.events[0].ty, "refund");
+    assert_eq!(balance(&deps, "alice"), 100);
+    assert_eq!(load_or_zero(&deps, CHANNEL_ESCROW, DATA_CHANNEL), 0);
+    assert!(!IN_FLIGHT.has(&deps.storage, envelope.nonce));
+}
+
+#[test]
+fn successful_ack_keeps_tokens_escrowed() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    let res = transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 40).unwrap();
+
+    let envelope = sent_packet(&res);
+    let msg = mock_ibc_packet_ack(DATA_CHANNEL, &envelope, transfer_ack(40)).unwrap();
+    ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(balance(&deps, "alice"), 60);
+    assert_eq!(load_or_zero(&deps, CHANNEL_ESCROW, DATA_CHANNEL), 40);
+    assert!(!IN_FLIGHT.has(&deps.storage, envelope.nonce));
+}
+
+#[test]
+fn packets_are_refunded_once() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    let res = transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 40).unwrap();
+
+    let envelope = sent_packet(&res);
+    let msg = mock_ibc_packet_ack(DATA_CHANNEL, &envelope, error_ack()).unwrap();
+    ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+    let msg = mock_ibc_packet_timeout(DATA_CHANNEL, &envelope).unwrap();
+    let res = ibc_packet_timeout(deps.as_mut(), mock_env(), msg).unwrap();
+    assert!(res.events.is_empty());
+    assert_eq!(balance(&deps, "alice"), 100);
+}
+
+fn open_with(port: &str, connection: &str) -> IbcChannelOpenMsg {
+    let mut channel = mock_ibc_channel("channel-7", IbcOrder::Unordered, IBC_VERSION);
+    channel.counterparty_endpoint.port_id = port.to_string();
+    channel.connection_id = connection.to_string();
+    IbcChannelOpenMsg::new_init(channel)
+}
+
+#[test]
+fn handshake_only_accepts_allowed_chains() {
+    let mut deps = setup(true, &[]);
+    let msg = mock_ibc_channel_open_try("channel-7", IbcOrder::Unordered, IBC_VERSION);
+    ibc_channel_open(deps.as_mut(), mock_env(), msg).unwrap();
+
+    let err = ibc_channel_open(
+        deps.as_mut(),
+        mock_env(),
+        open_with("other_port", "connection-9"),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::CounterpartyNotAllowed { port_id, connection_id }
+            if port_id == "other_port" && connection_id == "connection-9"
+    ));
+
+    // a chain can also be allowed by its connection
+    let info = mock_info("creator", &[]);
+    let msg = ExecuteMsg::AddChain {
+        chain: "connection-9".to_string(),
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    ibc_channel_open(
+        deps.as_mut(),
+        mock_env(),
+        open_with("other_port", "connection-9"),
+    )
+    .unwrap();
+}
+
+#[test]
+fn only_the_admin_manages_chains() {
+    let mut deps = setup(true, &[]);
+    let add = |chain: &str| ExecuteMsg::AddChain {
+        chain: chain.to_string(),
+    };
+    let remove = |chain: &str| ExecuteMsg::RemoveChain {
+        chain: chain.to_string(),
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        add("other_port"),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    let admin = mock_info("creator", &[]);
+    let err = execute(deps.as_mut(), mock_env(), admin.clone(), add("their_port")).unwrap_err();
+    assert!(matches!(err, ContractError::ChainAlreadyAllowed { .. }));
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        admin.clone(),
+        remove("their_port"),
+    )
+    .unwrap();
+    let msg = mock_ibc_channel_open_try("channel-7", IbcOrder::Unordered, IBC_VERSION);
+    let err = ibc_channel_open(deps.as_mut(), mock_env(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::CounterpartyNotAllowed { .. }));
+
+    let err = execute(deps.as_mut(), mock_env(), admin, remove("their_port")).unwrap_err();
+    assert!(matches!(err, ContractError::ChainNotAllowed { .. }));
+}
+
+#[test]
+fn version_is_negotiated_during_the_handshake() {
+    let mut deps = setup(true, &[]);
+
+    // nothing proposed, we offer the latest version
+    let channel = mock_ibc_channel("channel-7", IbcOrder::Unordered, "");
+    let res = ibc_channel_open(
+        deps.as_mut(),
+        mock_env(),
+        IbcChannelOpenMsg::new_init(channel),
+    )
+    .unwrap();
+    assert_eq!(res.unwrap().version, IBC_VERSION);
+
+    // on OpenTry we follow the counterparty
+    let channel = mock_ibc_channel("channel-7", IbcOrder::Unordered, "");
+    let msg = IbcChannelOpenMsg::new_try(channel, IBC_VERSION);
+    let res = ibc_channel_open(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(res.unwrap().version, IBC_VERSION);
+
+    let channel = mock_ibc_channel("channel-7", IbcOrder::Unordered, IBC_VERSION);
+    let msg = IbcChannelOpenMsg::new_try(channel, "counter-1");
+    let err = ibc_channel_open(deps.as_mut(), mock_env(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidVersion { actual, .. } if actual == "counter-1"));
+
+    let channel = mock_ibc_channel("channel-7", IbcOrder::Unordered, "counter-1");
+    let msg = IbcChannelOpenMsg::new_init(channel);
+    let err = ibc_channel_open(deps.as_mut(), mock_env(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidVersion { .. }));
+
+    let channel = mock_ibc_channel("channel-7", IbcOrder::Ordered, IBC_VERSION);
+    let msg = IbcChannelOpenMsg::new_init(channel);
+    let err = ibc_channel_open(deps.as_mut(), mock_env(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::OrderedChannel {}));
+}
+
+#[test]
+fn connect_stores_the_agreed_version() {
+    let mut deps = setup(true, &[]);
+    assert_eq!(
+        CHANNELS
+            .load(&deps.storage, DATA_CHANNEL.to_string())
+            .unwrap()
+            .version,
+        IBC_VERSION
+    );
+
+    let channel = mock_ibc_channel("channel-8", IbcOrder::Unordered, IBC_VERSION);
+    let msg = IbcChannelConnectMsg::new_ack(channel, "counter-1");
+    let err = ibc_channel_connect(deps.as_mut(), mock_env(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidVersion { .. }));
+    assert!(!CHANNELS.has(&deps.storage, "channel-8".to_string()));
+}
+
+fn query_channel(deps: &TestDeps, id: &str) -> ChannelInfo {
+    let msg = QueryMsg::Channel { id: id.to_string() };
+    from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap()
+}
+
+fn list_channels(deps: &TestDeps, start_after: Option<&str>) -> Vec<ChannelInfo> {
+    let msg = QueryMsg::ListChannels {
+        start_after: start_after.map(String::from),
+        limit: None,
+    };
+    let res: ListChannelsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    res.channels
+}
+
+#[test]
+fn channels_are_listed_after_connect_and_close() {
+    let mut deps = setup(true, &[]);
+    connect(deps.as_mut(), "channel-2");
+
+    let info = query_channel(&deps, DATA_CHANNEL);
+    assert_eq!(info.counterparty_port_id, "their_port");
+    assert_eq!(info.connection_id, "connection-2");
+    assert_eq!(info.version, IBC_VERSION);
+    assert_eq!(info.opened_at, mock_env().block.time);
+    assert_eq!(info.status, ChannelStatus::Open);
+
+    let ids: Vec<_> = list_channels(&deps, None)
+        .into_iter()
+        .map(|channel| channel.id)
+        .collect();
+    assert_eq!(ids, vec![DATA_CHANNEL, "channel-2"]);
+    let ids: Vec<_> = list_channels(&deps, Some(DATA_CHANNEL))
+        .into_iter()
+        .map(|channel| channel.id)
+        .collect();
+    assert_eq!(ids, vec!["channel-2"]);
+
+    close(deps.as_mut(), "channel-2");
+    assert_eq!(
+        query_channel(&deps, "channel-2").status,
+        ChannelStatus::Closed
+    );
+    assert_eq!(list_channels(&deps, None).len(), 2);
+}
+
+#[test]
+fn admin_starts_closing_channels() {
+    let mut deps = setup(true, &[]);
+    let close = ExecuteMsg::CloseChannel {
+        channel: DATA_CHANNEL.to_string(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        close.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("creator", &[]),
+        close.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Ibc(IbcMsg::CloseChannel {
+            channel_id: DATA_CHANNEL.to_string()
+        })
+    );
+    assert_eq!(
+        query_channel(&deps, DATA_CHANNEL).status,
+        ChannelStatus::Closing
+    );
+
+    let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), close).unwrap_err();
+    assert!(matches!(err, ContractError::ChannelNotOpen { .. }));
+    let msg = ExecuteMsg::CloseChannel {
+        channel: "channel-9".to_string(),
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::UnknownChannel { .. }));
+}
+
+fn close(deps: DepsMut, channel: &str) -> IbcBasicResponse {
+    let msg = mock_ibc_channel_close_confirm(channel, IbcOrder::Unordered, IBC_VERSION);
+    ibc_channel_close(deps, mock_env(), msg).unwrap()
+}
+
+#[test]
+fn in_flight_packets_wait_for_their_timeout_on_close() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    let res = transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 40).unwrap();
+    let envelope = sent_packet(&res);
+
+    // the counterparty may have credited the packet already
+    let res = close(deps.as_mut(), DATA_CHANNEL);
+    assert!(res.events.is_empty());
+    assert_eq!(balance(&deps, "alice"), 60);
+    assert_eq!(load_or_zero(&deps, CHANNEL_ESCROW, DATA_CHANNEL), 40);
+
+    // only a timeout proves it never arrived
+    let msg = mock_ibc_packet_timeout(DATA_CHANNEL, &envelope).unwrap();
+    let res = ibc_packet_timeout(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(res.events[0].ty, "refund");
+    assert_eq!(balance(&deps, "alice"), 100);
+    assert_eq!(load_or_zero(&deps, CHANNEL_ESCROW, DATA_CHANNEL), 0);
+}
+
+#[test]
+fn closed_channel_carries_no_packets() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    close(deps.as_mut(), DATA_CHANNEL);
+
+    let err = transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 40).unwrap_err();
+    assert!(matches!(err, ContractError::ChannelNotOpen { .. }));
+    let msg = IbcExecuteMsg::IncreaseAllowance {
+        spender: "spender".to_string(),
+        amount: Uint128::new(30),
+        expires: None,
+    };
+    let res = receive(deps.as_mut(), DATA_CHANNEL, "alice", msg);
+    let err = parse_ack(&res.acknowledgement).unwrap_err();
+    assert_eq!(err.code, ErrorCode::ChannelNotOpen);
+}
+
+#[test]
+fn admin_redeems_balances_of_closed_channels() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    let account = remote_account(&deps.api, DATA_CHANNEL, "dave").unwrap();
+    let msg = ExecuteMsg::Transfer {
+        recipient: account.to_string(),
+        amount: Uint128::new(25),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+    let redeem = ExecuteMsg::Redeem {
+        channel: DATA_CHANNEL.to_string(),
+        remote_sender: "dave".to_string(),
+        recipient: "erin".to_string(),
+    };
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("creator", &[]),
+        redeem.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::ChannelNotClosed { .. }));
+
+    close(deps.as_mut(), DATA_CHANNEL);
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("alice", &[]),
+        redeem.clone(),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("creator", &[]),
+        redeem.clone(),
+    )
+    .unwrap();
+    assert_eq!(balance(&deps, account.as_str()), 0);
+    assert_eq!(balance(&deps, "erin"), 25);
+
+    let err = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), redeem).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidZeroAmount {}));
+}
+
+#[test]
+fn stats_count_every_packet() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    let sent: Vec<_> = (0..3)
+        .map(|_| sent_packet(&transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 10).unwrap()))
+        .collect();
+    let msg = mock_ibc_packet_ack(DATA_CHANNEL, &sent[0], transfer_ack(10)).unwrap();
+    ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+    let msg = mock_ibc_packet_ack(DATA_CHANNEL, &sent[1], error_ack()).unwrap();
+    ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+    let msg = mock_ibc_packet_timeout(DATA_CHANNEL, &sent[2]).unwrap();
+    ibc_packet_timeout(deps.as_mut(), mock_env(), msg).unwrap();
+
+    receive(
+        deps.as_mut(),
+        DATA_CHANNEL,
+        "remote",
+        transfer_packet("bob", 4),
+    );
+    // more than was ever escrowed, answered with an error ack
+    let res = receive(
+        deps.as_mut(),
+        DATA_CHANNEL,
+        "remote",
+        transfer_packet("bob", 50),
+    );
+    let err = parse_ack(&res.acknowledgement).unwrap_err();
+    assert_eq!(err.code, ErrorCode::Overflow);
+
+    let msg = QueryMsg::ChannelStats {
+        channel: DATA_CHANNEL.to_string(),
+    };
+    let stats: ChannelStats = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(
+        stats,
+        ChannelStats {
+            packets_sent: 3,
+            packets_received: 2,
+            acks_ok: 1,
+            acks_error: 1,
+            timeouts: 1,
+            volume_sent: Uint128::new(30),
+            volume_received: Uint128::new(4),
+        }
+    );
+
+    let msg = QueryMsg::ChannelStats {
+        channel: "channel-9".to_string(),
+    };
+    let stats: ChannelStats = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(stats, ChannelStats::default());
+}
+
+#[test]
+fn success_acks_carry_the_packet_result() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 40).unwrap();
+    let res = receive(
+        deps.as_mut(),
+        DATA_CHANNEL,
+        "remote",
+        transfer_packet("bob", 15),
+    );
+    let result: PacketResult = from_binary(&parse_ack(&res.acknowledgement).unwrap()).unwrap();
+    assert_eq!(
+        result,
+        PacketResult::Transfer {
+            recipient: "bob".to_string(),
+            balance: Uint128::new(15),
+        }
+    );
+
+    let msg = IbcExecuteMsg::IncreaseAllowance {
+        spender: "spender".to_string(),
+        amount: Uint128::new(30),
+        expires: None,
+    };
+    let res = receive(deps.as_mut(), DATA_CHANNEL, "remote", msg);
+    let result: PacketResult = from_binary(&parse_ack(&res.acknowledgement).unwrap()).unwrap();
+    assert_eq!(
+        result,
+        PacketResult::IncreaseAllowance {
+            spender: "spender".to_string(),
+            allowance: Uint128::new(30),
+            expires: Expiration::Never {},
+        }
+    );
+}
+
+#[test]
+fn packet_results_are_decoded_on_ack() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    let res = transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 40).unwrap();
+    let msg = mock_ibc_packet_ack(DATA_CHANNEL, &sent_packet(&res), transfer_ack(40)).unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+    let event = &res.events[0];
+    assert_eq!(event.ty, "packet_result");
+    let attrs: Vec<_> = event
+        .attributes
+        .iter()
+        .map(|attr| (attr.key.as_str(), attr.value.as_str()))
+        .collect();
+    assert_eq!(
+        attrs,
+        vec![
+            ("action", "transfer"),
+            ("recipient", "bob"),
+            ("balance", "40")
+        ]
+    );
+
+    // older counterparties acknowledge without a result
+    let res = transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 10).unwrap();
+    let ack = IbcAcknowledgement::new(to_binary(&Ack::Result(b"1".into())).unwrap());
+    let msg = mock_ibc_packet_ack(DATA_CHANNEL, &sent_packet(&res), ack).unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+    assert!(res.events.is_empty());
+    assert_eq!(balance(&deps, "alice"), 50);
+}
+
+#[test]
+fn error_acks_carry_a_code() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    let msg = IbcExecuteMsg::DecreaseAllowance {
+        spender: "spender".to_string(),
+        amount: Uint128::new(30),
+        expires: None,
+    };
+    let res = receive(deps.as_mut(), DATA_CHANNEL, "alice", msg);
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "error_code" && attr.value == "std"));
+    let err = parse_ack(&res.acknowledgement).unwrap_err();
+    assert_eq!(err.code, ErrorCode::Std);
+
+    let res = transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 40).unwrap();
+    let msg = mock_ibc_packet_ack(DATA_CHANNEL, &sent_packet(&res), error_ack()).unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "error_code" && attr.value == "unauthorized"));
+    assert_eq!(res.events[0].attributes[3].value, "Unauthorized");
+}
+
+#[test]
+fn free_text_errors_have_no_code() {
+    let err = parse_ack_error("insufficient funds");
+    assert_eq!(err.code, ErrorCode::Unknown);
+    assert_eq!(err.message, "insufficient funds");
+}
+
+#[test]
+fn error_codes_survive_the_ack() {
+    let codes = [
+        ErrorCode::Unknown,
+        ErrorCode::Std,
+        ErrorCode::InvalidPacket,
+        ErrorCode::Overflow,
+        ErrorCode::OrderedChannel,
+        ErrorCode::UnorderedControlChannel,
+        ErrorCode::WrongChannelType,
+        ErrorCode::InvalidVersion,
+        ErrorCode::Unauthorized,
+        ErrorCode::CannotSetOwnAccount,
+        ErrorCode::InvalidZeroAmount,
+        ErrorCode::Expired,
+        ErrorCode::NoAllowance,
+        ErrorCode::CannotExceedCap,
+        ErrorCode::LogoTooBig,
+        ErrorCode::InvalidXmlPreamble,
+        ErrorCode::InvalidPngHeader,
+        ErrorCode::InvalidExpiration,
+        ErrorCode::DuplicateInitialBalanceAddresses,
+        ErrorCode::UnknownChannel,
+        ErrorCode::ChannelNotOpen,
+        ErrorCode::ChannelNotClosed,
+        ErrorCode::CounterpartyNotAllowed,
+        ErrorCode::ChainAlreadyAllowed,
+        ErrorCode::ChainNotAllowed,
+        ErrorCode::NotMainDeployment,
+        ErrorCode::UnknownDenom,
+        ErrorCode::NotIcs20Channel,
+        ErrorCode::ReceiveHookFailed,
+        ErrorCode::CallbackFailed,
+        ErrorCode::CallbackNotSupported,
+        ErrorCode::Ics20Channel,
+        ErrorCode::RateLimitExceeded,
+        ErrorCode::Paused,
+        ErrorCode::IbcReceivePaused,
+        ErrorCode::IbcSendPaused,
+        ErrorCode::ChannelPaused,
+        ErrorCode::NoPendingOwner,
+        ErrorCode::NoPendingMinter,
+        ErrorCode::MinterProposalExpired,
+        ErrorCode::NotSatellite,
+        ErrorCode::NotMainChannel,
+        ErrorCode::RemoteDenomOnMain,
+        ErrorCode::CallbackToSelf,
+        ErrorCode::UntrustedController,
+        ErrorCode::InvalidRateLimit,
+        ErrorCode::RenounceWhilePaused,
+        ErrorCode::VoucherBurn,
+    ];
+    for code in codes {
+        let err = AckError {
+            code,
+            message: "boom".to_string(),
+        };
+        let ack = String::from_utf8(to_vec(&err).unwrap()).unwrap();
+        assert!(ack.contains(&format!("\"{}\"", code)));
+        assert_eq!(parse_ack_error(&ack), err);
+    }
+}
+
+#[test]
+fn failing_send_hook_is_rolled_back() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    let account = remote_account(&deps.api, DATA_CHANNEL, "dave").unwrap();
+    let msg = ExecuteMsg::Transfer {
+        recipient: account.to_string(),
+        amount: Uint128::new(25),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+
+    let msg = IbcExecuteMsg::Send {
+        contract: "hook".to_string(),
+        amount: Uint128::new(10),
+        msg: Binary::from(b"{}".to_vec()),
+    };
+    let res = receive(deps.as_mut(), DATA_CHANNEL, "dave", msg);
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(res.messages[0].id, SEND_REPLY_ID);
+    assert_eq!(res.messages[0].reply_on, ReplyOn::Error);
+    assert!(matches!(
+        &res.messages[0].msg,
+        CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) if contract_addr == "hook"
+    ));
+    assert_eq!(balance(&deps, account.as_str()), 15);
+    assert_eq!(balance(&deps, "hook"), 10);
+
+    let msg = Reply {
+        id: SEND_REPLY_ID,
+        result: SubMsgResult::Err("boom".to_string()),
+    };
+    let res = reply(deps.as_mut(), mock_env(), msg).unwrap();
+    let err = parse_ack(&res.data.unwrap()).unwrap_err();
+    assert_eq!(err.code, ErrorCode::ReceiveHookFailed);
+    assert_eq!(balance(&deps, account.as_str()), 25);
+    assert_eq!(balance(&deps, "hook"), 0);
+}
+
+#[test]
+fn hook_and_callback_replies_only_expect_failures() {
+    let mut deps = setup(true, &[]);
+    for id in [SEND_REPLY_ID, TRANSFER_CALLBACK_REPLY_ID] {
+        let msg = Reply {
+            id,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        };
+        let err = reply(deps.as_mut(), mock_env(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Std(_)));
+    }
+}
+
+fn hook() -> Callback {
+    Callback {
+        contract: "hook".to_string(),
+        msg: Binary::from(b"{}".to_vec()),
+    }
+}
+
+#[test]
+fn transfer_callback_runs_after_the_credit() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 40).unwrap();
+
+    let msg = IbcExecuteMsg::Transfer {
+        receipient: "bob".to_string(),
+        amount: Uint128::new(10),
+        memo: Some("for lunch".to_string()),
+        callback: Some(hook()),
+    };
+    let res = receive(deps.as_mut(), DATA_CHANNEL, "remote", msg);
+    parse_ack(&res.acknowledgement).unwrap();
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "memo" && attr.value == "for lunch"));
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            ReceiveCallbackMsg {
+                recipient: "bob".to_string(),
+                amount: Uint128::new(10),
+                channel: DATA_CHANNEL.to_string(),
+                remote_sender: "remote".to_string(),
+                msg: hook().msg,
+            }
+            .into_cosmos_msg("hook")
+            .unwrap(),
+            TRANSFER_CALLBACK_REPLY_ID
+        )]
+    );
+    assert_eq!(balance(&deps, "bob"), 10);
+}
+
+#[test]
+fn transfer_callbacks_cannot_target_the_token() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 40).unwrap();
+    let msg = IbcExecuteMsg::Transfer {
+        receipient: "bob".to_string(),
+        amount: Uint128::new(10),
+        memo: None,
+        callback: Some(Callback {
+            contract: MOCK_CONTRACT_ADDR.to_string(),
+            msg: to_binary(&ExecuteMsg::Transfer {
+                recipient: "mallory".to_string(),
+                amount: Uint128::new(30),
+            })
+            .unwrap(),
+        }),
+    };
+    let res = receive(deps.as_mut(), DATA_CHANNEL, "remote", msg);
+    let err = parse_ack(&res.acknowledgement).unwrap_err();
+    assert_eq!(err.code, ErrorCode::CallbackToSelf);
+    assert!(res.messages.is_empty());
+}
+
+#[test]
+fn failing_transfer_callback_rolls_back_the_credit() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 40).unwrap();
+    let msg = IbcExecuteMsg::Transfer {
+        receipient: "bob".to_string(),
+        amount: Uint128::new(10),
+        memo: None,
+        callback: Some(hook()),
+    };
+    receive(deps.as_mut(), DATA_CHANNEL, "remote", msg);
+
+    let msg = Reply {
+        id: TRANSFER_CALLBACK_REPLY_ID,
+        result: SubMsgResult::Err("boom".to_string()),
+    };
+    let res = reply(deps.as_mut(), mock_env(), msg).unwrap();
+    let err = parse_ack(&res.data.unwrap()).unwrap_err();
+    assert_eq!(err.code, ErrorCode::CallbackFailed);
+    assert_eq!(balance(&deps, "bob"), 0);
+    assert_eq!(load_or_zero(&deps, CHANNEL_ESCROW, DATA_CHANNEL), 40);
+    let stats = CHANNEL_STATS
+        .load(&deps.storage, DATA_CHANNEL.to_string())
+        .unwrap();
+    assert_eq!(stats.volume_received, Uint128::zero());
+}
+
+fn transfer_with_ack_callback(deps: DepsMut) -> PacketEnvelope {
+    let msg = ExecuteMsg::TransferRemote {
+        channel: DATA_CHANNEL.to_string(),
+        recipient: "bob".to_string(),
+        amount: Uint128::new(40),
+        timeout: None,
+        memo: None,
+        callback: None,
+        ack_callback: Some(hook()),
+    };
+    let res = execute(deps, mock_env(), mock_info("alice", &[]), msg).unwrap();
+    sent_packet(&res)
+}
+
+#[cw_serde]
+enum HookMsg {
+    TransferCallback(TransferCallbackMsg),
+}
+
+fn ack_callback_sent(res: &IbcBasicResponse) -> (TransferCallbackMsg, u64) {
+    let sub = &res.messages[0];
+    let msg = match &sub.msg {
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr, msg, ..
+        }) => {
+            assert_eq!(contract_addr, "hook");
+            msg
+        }
+        msg => panic!("unexpected message {:?}", msg),
+    };
+    let HookMsg::TransferCallback(callback) = from_binary(msg).unwrap();
+    (callback, sub.id)
+}
+
+#[test]
+fn ack_callback_reports_how_the_transfer_ended() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    let envelope = transfer_with_ack_callback(deps.as_mut());
+    let msg = mock_ibc_packet_ack(DATA_CHANNEL, &envelope, transfer_ack(40)).unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+    let (callback, id) = ack_callback_sent(&res);
+    assert_eq!(id, ACK_CALLBACK_REPLY_ID);
+    assert_eq!(
+        callback,
+        TransferCallbackMsg {
+            sender: "alice".to_string(),
+            channel: DATA_CHANNEL.to_string(),
+            amount: Uint128::new(40),
+            success: true,
+            error: None,
+            msg: hook().msg,
+        }
+    );
+
+    let envelope = transfer_with_ack_callback(deps.as_mut());
+    let msg = mock_ibc_packet_timeout(DATA_CHANNEL, &envelope).unwrap();
+    let res = ibc_packet_timeout(deps.as_mut(), mock_env(), msg).unwrap();
+    let (callback, _) = ack_callback_sent(&res);
+    assert!(!callback.success);
+    assert_eq!(callback.error.as_deref(), Some("timeout"));
+
+    // a failing callback is only reported
+    let msg = Reply {
+        id: ACK_CALLBACK_REPLY_ID,
+        result: SubMsgResult::Err("boom".to_string()),
+    };
+    let res = reply(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(res.data, None);
+    assert_eq!(balance(&deps, "alice"), 60);
+}
+
+fn remote_allowance(deps: &TestDeps, owner: &str, spender: &str) -> AllowanceResponse {
+    let msg = QueryMsg::RemoteAllowance {
+        channel: DATA_CHANNEL.to_string(),
+        owner: owner.to_string(),
+        spender: spender.to_string(),
+    };
+    from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap()
+}
+
+fn ack_remote(deps: &mut TestDeps, res: &Response, result: PacketResult) {
+    let ack = IbcAcknowledgement::new(make_ack_success(&result));
+    let msg = mock_ibc_packet_ack(DATA_CHANNEL, &sent_packet(res), ack).unwrap();
+    ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+}
+
+#[test]
+fn remote_allowances_are_mirrored_once_acknowledged() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    let expires = Expiration::AtHeight(500);
+    let msg = ExecuteMsg::IncreaseRemoteAllowance {
+        channel: DATA_CHANNEL.to_string(),
+        spender: "bob".to_string(),
+        amount: Uint128::new(30),
+        expires: Some(expires),
+        timeout: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+    let envelope = sent_packet(&res);
+    assert_eq!(envelope.sender, "alice");
+    assert_eq!(
+        envelope.msg,
+        IbcExecuteMsg::IncreaseAllowance {
+            spender: "bob".to_string(),
+            amount: Uint128::new(30),
+            expires: Some(expires),
+        }
+    );
+    // nothing is known until the counterparty acknowledges
+    assert_eq!(remote_allowance(&deps, "alice", "bob"), Default::default());
+
+    ack_remote(
+        &mut deps,
+        &res,
+        PacketResult::IncreaseAllowance {
+            spender: "bob".to_string(),
+            allowance: Uint128::new(30),
+            expires,
+        },
+    );
+    assert_eq!(
+        remote_allowance(&deps, "alice", "bob"),
+        AllowanceResponse {
+            allowance: Uint128::new(30),
+            expires,
+        }
+    );
+
+    // a decrease down to zero forgets the allowance
+    let msg = ExecuteMsg::DecreaseRemoteAllowance {
+        channel: DATA_CHANNEL.to_string(),
+        spender: "bob".to_string(),
+        amount: Uint128::new(30),
+        expires: None,
+        timeout: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+    ack_remote(
+        &mut deps,
+        &res,
+        PacketResult::DecreaseAllowance {
+            spender: "bob".to_string(),
+            allowance: Uint128::zero(),
+            expires,
+        },
+    );
+    assert_eq!(remote_allowance(&deps, "alice", "bob"), Default::default());
+    // local balances are untouched
+    assert_eq!(balance(&deps, "alice"), 100);
+}
+
+#[test]
+fn spending_a_remote_allowance_keeps_its_expiration() {
+    let mut deps = setup(true, &[]);
+    let expires = Expiration::AtHeight(500);
+    let msg = ExecuteMsg::IncreaseRemoteAllowance {
+        channel: DATA_CHANNEL.to_string(),
+        spender: "alice".to_string(),
+        amount: Uint128::new(30),
+        expires: Some(expires),
+        timeout: None,
+    };
+    // carol grants alice an allowance on the counterparty
+    let res = execute(deps.as_mut(), mock_env(), mock_info("carol", &[]), msg).unwrap();
+    ack_remote(
+        &mut deps,
+        &res,
+        PacketResult::IncreaseAllowance {
+            spender: "alice".to_string(),
+            allowance: Uint128::new(30),
+            expires,
+        },
+    );
+
+    let msg = ExecuteMsg::TransferFromRemote {
+        channel: DATA_CHANNEL.to_string(),
+        owner: "carol".to_string(),
+        recipient: "bob".to_string(),
+        amount: Uint128::new(10),
+        timeout: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+    ack_remote(
+        &mut deps,
+        &res,
+        PacketResult::TransferFrom {
+            owner: "carol".to_string(),
+            recipient: "bob".to_string(),
+            owner_balance: Uint128::new(90),
+            allowance: Uint128::new(20),
+        },
+    );
+    assert_eq!(
+        remote_allowance(&deps, "carol", "alice"),
+        AllowanceResponse {
+            allowance: Uint128::new(20),
+            expires,
+        }
+    );
+
+    // a failed spend leaves the mirror alone
+    let msg = ExecuteMsg::BurnFromRemote {
+        channel: DATA_CHANNEL.to_string(),
+        owner: "carol".to_string(),
+        amount: Uint128::new(50),
+        timeout: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+    let msg = mock_ibc_packet_ack(DATA_CHANNEL, &sent_packet(&res), error_ack()).unwrap();
+    ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(
+        remote_allowance(&deps, "carol", "alice").allowance,
+        Uint128::new(20)
+    );
+}
+
+/// Lets the account acting for "dave" spend `amount` of alice's tokens.
+fn approve_remote_account(deps: &mut TestDeps, amount: u128) -> Addr {
+    let account = remote_account(&deps.api, DATA_CHANNEL, "dave").unwrap();
+    let msg = ExecuteMsg::IncreaseAllowance {
+        spender: account.to_string(),
+        amount: Uint128::new(amount),
+        expires: None,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+    account
+}
+
+fn allowance(deps: &TestDeps, spender: &Addr) -> Uint128 {
+    ALLOWANCES
+        .load(&deps.storage, (&Addr::unchecked("alice"), spender))
+        .unwrap()
+        .allowance
+}
+
+#[test]
+fn failing_send_from_hook_restores_the_allowance() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    let account = approve_remote_account(&mut deps, 30);
+
+    let msg = IbcExecuteMsg::SendFrom {
+        owner: "alice".to_string(),
+        contract: "hook".to_string(),
+        amount: Uint128::new(10),
+        msg: Binary::from(b"{}".to_vec()),
+    };
+    let res = receive(deps.as_mut(), DATA_CHANNEL, "dave", msg);
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(res.messages[0].id, SEND_REPLY_ID);
+    assert_eq!(res.messages[0].reply_on, ReplyOn::Error);
+    assert_eq!(balance(&deps, "alice"), 90);
+    assert_eq!(allowance(&deps, &account), Uint128::new(20));
+
+    let msg = Reply {
+        id: SEND_REPLY_ID,
+        result: SubMsgResult::Err("boom".to_string()),
+    };
+    let res = reply(deps.as_mut(), mock_env(), msg).unwrap();
+    let err = parse_ack(&res.data.unwrap()).unwrap_err();
+    assert_eq!(err.code, ErrorCode::ReceiveHookFailed);
+    assert_eq!(balance(&deps, "alice"), 100);
+    assert_eq!(balance(&deps, "hook"), 0);
+    assert_eq!(allowance(&deps, &account), Uint128::new(30));
+}
+
+#[test]
+fn spending_more_than_the_balance_keeps_the_allowance() {
+    let mut deps = setup(true, &[("alice", 10)]);
+    let account = approve_remote_account(&mut deps, 30);
+
+    let msg = IbcExecuteMsg::TransferFrom {
+        owner: "alice".to_string(),
+        recipient: "bob".to_string(),
+        amount: Uint128::new(20),
+    };
+    let res = receive(deps.as_mut(), DATA_CHANNEL, "dave", msg);
+    let err = parse_ack(&res.acknowledgement).unwrap_err();
+    assert_eq!(err.code, ErrorCode::Overflow);
+    assert_eq!(balance(&deps, "alice"), 10);
+    assert_eq!(allowance(&deps, &account), Uint128::new(30));
+}
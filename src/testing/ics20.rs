@@ -0,0 +1,272 @@
+use cosmwasm_std::testing::{
+    mock_env, mock_ibc_channel_connect_ack, mock_ibc_packet_ack, mock_ibc_packet_recv,
+    mock_ibc_packet_timeout, mock_info, MOCK_CONTRACT_ADDR,
+};
+use cosmwasm_std::{
+    from_binary, to_binary, Binary, CosmosMsg, IbcAcknowledgement, IbcMsg, IbcOrder,
+    IbcReceiveResponse, Response, StdResult, Uint128,
+};
+
+use super::helpers::*;
+use crate::ack::Ack;
+use crate::contract::{execute, query};
+use crate::ibc::{ibc_channel_connect, ibc_packet_ack, ibc_packet_receive, ibc_packet_timeout};
+use crate::ics20::{Ics20Packet, ICS20_VERSION};
+use crate::msg::{Callback, DenomTraceResponse, ExecuteMsg, QueryMsg};
+use crate::state::{CHANNEL_ESCROW, CHANNEL_OUTSTANDING, VOUCHER_SUPPLY};
+use crate::{ContractError, ErrorCode};
+
+const ICS20_CHANNEL: &str = "channel-5";
+
+fn setup_ics20(is_main: bool, initial_balances: &[(&str, u128)]) -> TestDeps {
+    let mut deps = setup(is_main, initial_balances);
+    let msg = mock_ibc_channel_connect_ack(ICS20_CHANNEL, IbcOrder::Unordered, ICS20_VERSION);
+    ibc_channel_connect(deps.as_mut(), mock_env(), msg).unwrap();
+    deps
+}
+
+fn sent_ics20_packet(res: &Response) -> Ics20Packet {
+    let data = res
+        .messages
+        .iter()
+        .find_map(|sub| match &sub.msg {
+            CosmosMsg::Ibc(IbcMsg::SendPacket { data, .. }) => Some(data.clone()),
+            _ => None,
+        })
+        .expect("no packet sent");
+    from_binary(&data).unwrap()
+}
+
+fn set_remote_denom(
+    deps: &mut TestDeps,
+    sender: &str,
+    channel: &str,
+) -> Result<Response, ContractError> {
+    let msg = ExecuteMsg::SetRemoteDenom {
+        channel: channel.to_string(),
+        denom: "uatom".to_string(),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg)
+}
+
+fn receive_ics20(deps: &mut TestDeps, denom: &str, amount: u128) -> IbcReceiveResponse {
+    let data = Ics20Packet {
+        denom: denom.to_string(),
+        amount: Uint128::new(amount),
+        sender: "remote".to_string(),
+        receiver: "bob".to_string(),
+        memo: None,
+    };
+    let packet = mock_ibc_packet_recv(ICS20_CHANNEL, &data).unwrap();
+    ibc_packet_receive(deps.as_mut(), mock_env(), packet).unwrap()
+}
+
+/// The denom of this token after a round trip, prefixed by the counterparty.
+fn returning_denom() -> String {
+    format!("their-port/channel-1234/cw20:{}", MOCK_CONTRACT_ADDR)
+}
+
+#[test]
+fn ics20_transfers_send_fungible_token_packets() {
+    let mut deps = setup_ics20(true, &[("alice", 100)]);
+    let res = transfer_remote(deps.as_mut(), "alice", ICS20_CHANNEL, 40).unwrap();
+    assert_eq!(
+        sent_ics20_packet(&res),
+        Ics20Packet {
+            denom: format!("cw20:{}", MOCK_CONTRACT_ADDR),
+            amount: Uint128::new(40),
+            sender: "alice".to_string(),
+            receiver: "bob".to_string(),
+            memo: None,
+        }
+    );
+    assert_eq!(balance(&deps, "alice"), 60);
+    assert_eq!(load_or_zero(&deps, CHANNEL_ESCROW, ICS20_CHANNEL), 40);
+}
+
+#[test]
+fn returning_tokens_are_released() {
+    let mut deps = setup_ics20(true, &[("alice", 100)]);
+    transfer_remote(deps.as_mut(), "alice", ICS20_CHANNEL, 40).unwrap();
+
+    let res = receive_ics20(&mut deps, &returning_denom(), 15);
+    assert_eq!(
+        parse_ack(&res.acknowledgement).unwrap(),
+        Binary::from(vec![1])
+    );
+    assert_eq!(balance(&deps, "bob"), 15);
+    assert_eq!(load_or_zero(&deps, CHANNEL_ESCROW, ICS20_CHANNEL), 25);
+    assert_eq!(supply(&deps), 100);
+}
+
+#[test]
+fn unknown_denoms_are_rejected() {
+    let mut deps = setup_ics20(true, &[("alice", 100)]);
+    transfer_remote(deps.as_mut(), "alice", ICS20_CHANNEL, 40).unwrap();
+
+    // a native token of the counterparty, and our token returning over
+    // another channel
+    for denom in ["uatom", "their-port/channel-9/cw20:cosmos2contract"] {
+        let res = receive_ics20(&mut deps, denom, 15);
+        let err = parse_ack(&res.acknowledgement).unwrap_err();
+        assert_eq!(err.code, ErrorCode::UnknownDenom);
+    }
+    assert_eq!(balance(&deps, "bob"), 0);
+    assert_eq!(load_or_zero(&deps, CHANNEL_ESCROW, ICS20_CHANNEL), 40);
+}
+
+#[test]
+fn ics20_error_ack_refunds_sender() {
+    let mut deps = setup_ics20(true, &[("alice", 100)]);
+    let res = transfer_remote(deps.as_mut(), "alice", ICS20_CHANNEL, 40).unwrap();
+
+    // the transfer module sends free-form errors
+    let ack = IbcAcknowledgement::new(to_binary(&Ack::Error("invalid receiver".into())).unwrap());
+    let msg = mock_ibc_packet_ack(ICS20_CHANNEL, &sent_ics20_packet(&res), ack).unwrap();
+    let res = ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(res.events[0].ty, "refund");
+    assert!(res
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "error_code" && attr.value == "unknown"));
+    assert_eq!(balance(&deps, "alice"), 100);
+    assert_eq!(load_or_zero(&deps, CHANNEL_ESCROW, ICS20_CHANNEL), 0);
+}
+
+#[test]
+fn ics20_timeout_refunds_sender() {
+    let mut deps = setup_ics20(true, &[("alice", 100)]);
+    let res = transfer_remote(deps.as_mut(), "alice", ICS20_CHANNEL, 40).unwrap();
+
+    let msg = mock_ibc_packet_timeout(ICS20_CHANNEL, &sent_ics20_packet(&res)).unwrap();
+    let res = ibc_packet_timeout(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(res.events[0].ty, "refund");
+    assert_eq!(balance(&deps, "alice"), 100);
+    assert_eq!(load_or_zero(&deps, CHANNEL_ESCROW, ICS20_CHANNEL), 0);
+    assert_eq!(load_or_zero(&deps, CHANNEL_OUTSTANDING, ICS20_CHANNEL), 0);
+}
+
+#[test]
+fn ics20_success_ack_settles_the_transfer() {
+    let mut deps = setup_ics20(true, &[("alice", 100)]);
+    let res = transfer_remote(deps.as_mut(), "alice", ICS20_CHANNEL, 40).unwrap();
+
+    let ack = IbcAcknowledgement::new(to_binary(&Ack::Result(vec![1].into())).unwrap());
+    let msg = mock_ibc_packet_ack(ICS20_CHANNEL, &sent_ics20_packet(&res), ack).unwrap();
+    ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(balance(&deps, "alice"), 60);
+    assert_eq!(load_or_zero(&deps, CHANNEL_ESCROW, ICS20_CHANNEL), 40);
+    assert_eq!(load_or_zero(&deps, CHANNEL_OUTSTANDING, ICS20_CHANNEL), 40);
+}
+
+#[test]
+fn remote_denoms_round_trip() {
+    let mut deps = setup_ics20(false, &[]);
+    let err = set_remote_denom(&mut deps, "alice", ICS20_CHANNEL).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    let err = set_remote_denom(&mut deps, "creator", DATA_CHANNEL).unwrap_err();
+    assert!(matches!(err, ContractError::NotIcs20Channel { .. }));
+    set_remote_denom(&mut deps, "creator", ICS20_CHANNEL).unwrap();
+
+    // the token originates on the counterparty, we hold vouchers for it
+    let res = receive_ics20(&mut deps, "uatom", 50);
+    parse_ack(&res.acknowledgement).unwrap();
+    assert_eq!(balance(&deps, "bob"), 50);
+    assert_eq!(load_or_zero(&deps, VOUCHER_SUPPLY, ICS20_CHANNEL), 50);
+
+    // and send them back with their trace
+    let res = transfer_remote(deps.as_mut(), "bob", ICS20_CHANNEL, 20).unwrap();
+    assert_eq!(sent_ics20_packet(&res).denom, "my_port/channel-5/uatom");
+    assert_eq!(balance(&deps, "bob"), 30);
+    assert_eq!(load_or_zero(&deps, VOUCHER_SUPPLY, ICS20_CHANNEL), 30);
+}
+
+fn denom_trace(deps: &TestDeps, channel: &str) -> StdResult<DenomTraceResponse> {
+    let msg = QueryMsg::DenomTrace {
+        channel: channel.to_string(),
+    };
+    query(deps.as_ref(), mock_env(), msg).map(|res| from_binary(&res).unwrap())
+}
+
+#[test]
+fn main_deployment_has_no_remote_denom() {
+    let mut deps = setup_ics20(true, &[]);
+    let err = set_remote_denom(&mut deps, "creator", ICS20_CHANNEL).unwrap_err();
+    assert!(matches!(err, ContractError::RemoteDenomOnMain {}));
+}
+
+#[test]
+fn denom_trace_names_the_vouchers() {
+    let deps = setup_ics20(true, &[]);
+    assert_eq!(
+        denom_trace(&deps, ICS20_CHANNEL).unwrap(),
+        DenomTraceResponse {
+            path: "their_port/channel-7".to_string(),
+            base_denom: format!("cw20:{}", MOCK_CONTRACT_ADDR),
+            ibc_denom: "ibc/C1BEF87A744FF2B99FA9D6E0EB1DB4F53F5E1DF8EAC669C99D43066C0061B7B1"
+                .to_string(),
+            held_here: false,
+        }
+    );
+
+    let mut deps = setup_ics20(false, &[]);
+    set_remote_denom(&mut deps, "creator", ICS20_CHANNEL).unwrap();
+    assert_eq!(
+        denom_trace(&deps, ICS20_CHANNEL).unwrap(),
+        DenomTraceResponse {
+            path: "my_port/channel-5".to_string(),
+            base_denom: "uatom".to_string(),
+            ibc_denom: "ibc/A43494C6129C9A999B05EB8212968890CAB2A6E0BCEC8380E8D8DB23EC3465B4"
+                .to_string(),
+            held_here: true,
+        }
+    );
+
+    denom_trace(&deps, DATA_CHANNEL).unwrap_err();
+}
+
+#[test]
+fn ics20_channels_carry_no_callbacks() {
+    let mut deps = setup_ics20(true, &[("alice", 100)]);
+    let msg = ExecuteMsg::TransferRemote {
+        channel: ICS20_CHANNEL.to_string(),
+        recipient: "bob".to_string(),
+        amount: Uint128::new(40),
+        timeout: None,
+        memo: Some("for lunch".to_string()),
+        callback: Some(Callback {
+            contract: "hook".to_string(),
+            msg: Binary::from(b"{}".to_vec()),
+        }),
+        ack_callback: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::CallbackNotSupported { .. }));
+
+    // memos are part of ICS-20
+    let msg = ExecuteMsg::TransferRemote {
+        channel: ICS20_CHANNEL.to_string(),
+        recipient: "bob".to_string(),
+        amount: Uint128::new(40),
+        timeout: None,
+        memo: Some("for lunch".to_string()),
+        callback: None,
+        ack_callback: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+    assert_eq!(sent_ics20_packet(&res).memo.as_deref(), Some("for lunch"));
+}
+
+#[test]
+fn ics20_channels_only_carry_transfers() {
+    let mut deps = setup_ics20(true, &[("alice", 100)]);
+    let msg = ExecuteMsg::IncreaseRemoteAllowance {
+        channel: ICS20_CHANNEL.to_string(),
+        spender: "bob".to_string(),
+        amount: Uint128::new(30),
+        expires: None,
+        timeout: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::Ics20Channel { .. }));
+}
@@ -0,0 +1,154 @@
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{from_binary, Uint128};
+use cw20::MinterResponse;
+use cw_utils::Expiration;
+
+use super::helpers::*;
+use crate::contract::{execute, instantiate, query};
+use crate::msg::{ExecuteMsg, InstantiateMsg, PendingMinterResponse, QueryMsg};
+use crate::ContractError;
+
+fn setup_minter() -> TestDeps {
+    let mut deps = mock_deps();
+    let msg = InstantiateMsg {
+        name: "Cross Chain Token".to_string(),
+        symbol: "CCT".to_string(),
+        decimals: 6,
+        mint: Some(MinterResponse {
+            minter: "minter".to_string(),
+            cap: Some(Uint128::new(1000)),
+        }),
+        is_main: true,
+        ..Default::default()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    deps
+}
+
+fn run(deps: &mut TestDeps, sender: &str, msg: ExecuteMsg) -> Result<(), ContractError> {
+    execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg)?;
+    Ok(())
+}
+
+fn propose(
+    deps: &mut TestDeps,
+    sender: &str,
+    expiry: Option<Expiration>,
+) -> Result<(), ContractError> {
+    let msg = ExecuteMsg::ProposeMinter {
+        new_minter: "next".to_string(),
+        expiry,
+    };
+    run(deps, sender, msg)
+}
+
+fn minter(deps: &TestDeps) -> Option<MinterResponse> {
+    from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Minter {}).unwrap()).unwrap()
+}
+
+fn pending_minter(deps: &TestDeps) -> Option<PendingMinterResponse> {
+    from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PendingMinter {}).unwrap()).unwrap()
+}
+
+fn mint(deps: &mut TestDeps, sender: &str) -> Result<(), ContractError> {
+    let msg = ExecuteMsg::Mint {
+        recipient: "alice".to_string(),
+        amount: Uint128::new(10),
+    };
+    run(deps, sender, msg)
+}
+
+#[test]
+fn minter_changes_once_accepted() {
+    let mut deps = setup_minter();
+    let err = propose(&mut deps, "alice", None).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    propose(&mut deps, "minter", None).unwrap();
+    assert_eq!(
+        pending_minter(&deps),
+        Some(PendingMinterResponse {
+            pending_minter: "next".to_string(),
+            expiry: Expiration::Never {},
+        })
+    );
+    // the current minter keeps minting until then
+    mint(&mut deps, "minter").unwrap();
+
+    let err = run(&mut deps, "alice", ExecuteMsg::AcceptMinter {}).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    run(&mut deps, "next", ExecuteMsg::AcceptMinter {}).unwrap();
+    assert_eq!(
+        minter(&deps),
+        Some(MinterResponse {
+            minter: "next".to_string(),
+            cap: Some(Uint128::new(1000)),
+        })
+    );
+    assert_eq!(pending_minter(&deps), None);
+
+    let err = mint(&mut deps, "minter").unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    mint(&mut deps, "next").unwrap();
+}
+
+#[test]
+fn expired_proposals_are_rejected() {
+    let mut deps = setup_minter();
+    let block = mock_env().block;
+    let err = propose(
+        &mut deps,
+        "minter",
+        Some(Expiration::AtHeight(block.height)),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::MinterProposalExpired {}));
+
+    propose(
+        &mut deps,
+        "minter",
+        Some(Expiration::AtHeight(block.height + 10)),
+    )
+    .unwrap();
+    let mut env = mock_env();
+    env.block.height += 10;
+    let err = execute(
+        deps.as_mut(),
+        env,
+        mock_info("next", &[]),
+        ExecuteMsg::AcceptMinter {},
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::MinterProposalExpired {}));
+    assert_eq!(minter(&deps).unwrap().minter, "minter");
+}
+
+#[test]
+fn minter_cancels_its_proposal() {
+    let mut deps = setup_minter();
+    let err = run(&mut deps, "minter", ExecuteMsg::CancelMinterProposal {}).unwrap_err();
+    assert!(matches!(err, ContractError::NoPendingMinter {}));
+
+    propose(&mut deps, "minter", None).unwrap();
+    let err = run(&mut deps, "next", ExecuteMsg::CancelMinterProposal {}).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    run(&mut deps, "minter", ExecuteMsg::CancelMinterProposal {}).unwrap();
+    assert_eq!(pending_minter(&deps), None);
+
+    let err = run(&mut deps, "next", ExecuteMsg::AcceptMinter {}).unwrap_err();
+    assert!(matches!(err, ContractError::NoPendingMinter {}));
+}
+
+#[test]
+fn renouncing_drops_the_proposal() {
+    let mut deps = setup_minter();
+    propose(&mut deps, "minter", None).unwrap();
+    run(&mut deps, "minter", ExecuteMsg::RenounceMinter {}).unwrap();
+    assert_eq!(minter(&deps), None);
+    assert_eq!(pending_minter(&deps), None);
+
+    let err = run(&mut deps, "next", ExecuteMsg::AcceptMinter {}).unwrap_err();
+    assert!(matches!(err, ContractError::NoPendingMinter {}));
+    let err = mint(&mut deps, "minter").unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+}
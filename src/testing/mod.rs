@@ -0,0 +1,10 @@
+mod contract;
+mod control;
+mod enumerable;
+mod helpers;
+mod ibc;
+mod ics20;
+mod minter;
+mod ownership;
+mod pause;
+mod rate_limit;
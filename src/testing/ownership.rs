@@ -0,0 +1,145 @@
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{from_binary, Addr};
+
+use super::helpers::*;
+use crate::contract::{execute, instantiate, query};
+use crate::msg::{ExecuteMsg, InstantiateMsg, PauseScope, PauseStatusResponse, QueryMsg};
+use crate::state::Ownership;
+use crate::ContractError;
+
+fn run(deps: &mut TestDeps, sender: &str, msg: ExecuteMsg) -> Result<(), ContractError> {
+    execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg)?;
+    Ok(())
+}
+
+fn propose(deps: &mut TestDeps, sender: &str, new_owner: &str) -> Result<(), ContractError> {
+    let msg = ExecuteMsg::ProposeOwner {
+        new_owner: new_owner.to_string(),
+    };
+    run(deps, sender, msg)
+}
+
+fn ownership(deps: &TestDeps) -> Ownership {
+    from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Ownership {}).unwrap()).unwrap()
+}
+
+fn add_chain(deps: &mut TestDeps, sender: &str) -> Result<(), ContractError> {
+    let msg = ExecuteMsg::AddChain {
+        chain: "connection-9".to_string(),
+    };
+    run(deps, sender, msg)
+}
+
+#[test]
+fn owner_defaults_to_the_instantiator() {
+    let deps = setup(true, &[]);
+    assert_eq!(
+        ownership(&deps),
+        Ownership {
+            owner: Some(Addr::unchecked("creator")),
+            pending_owner: None,
+        }
+    );
+
+    let mut deps = mock_deps();
+    let msg = InstantiateMsg {
+        name: "Cross Chain Token".to_string(),
+        symbol: "CCT".to_string(),
+        decimals: 6,
+        owner: Some("dao".to_string()),
+        ..Default::default()
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+    assert_eq!(ownership(&deps).owner, Some(Addr::unchecked("dao")));
+}
+
+#[test]
+fn ownership_moves_once_accepted() {
+    let mut deps = setup(true, &[]);
+    let err = propose(&mut deps, "alice", "alice").unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    let err = run(&mut deps, "dao", ExecuteMsg::AcceptOwner {}).unwrap_err();
+    assert!(matches!(err, ContractError::NoPendingOwner {}));
+
+    propose(&mut deps, "creator", "alice").unwrap();
+    // a new proposal replaces the previous one
+    propose(&mut deps, "creator", "dao").unwrap();
+    assert_eq!(ownership(&deps).pending_owner, Some(Addr::unchecked("dao")));
+    // the owner stays in charge until then
+    add_chain(&mut deps, "creator").unwrap();
+
+    let err = run(&mut deps, "alice", ExecuteMsg::AcceptOwner {}).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    run(&mut deps, "dao", ExecuteMsg::AcceptOwner {}).unwrap();
+    assert_eq!(
+        ownership(&deps),
+        Ownership {
+            owner: Some(Addr::unchecked("dao")),
+            pending_owner: None,
+        }
+    );
+
+    let err = add_chain(&mut deps, "creator").unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    let msg = ExecuteMsg::RemoveChain {
+        chain: "connection-9".to_string(),
+    };
+    run(&mut deps, "dao", msg).unwrap();
+}
+
+#[test]
+fn renouncing_leaves_no_owner() {
+    let mut deps = setup(true, &[]);
+    let err = run(&mut deps, "alice", ExecuteMsg::RenounceOwnership {}).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    propose(&mut deps, "creator", "dao").unwrap();
+    run(&mut deps, "creator", ExecuteMsg::RenounceOwnership {}).unwrap();
+    assert_eq!(
+        ownership(&deps),
+        Ownership {
+            owner: None,
+            pending_owner: None,
+        }
+    );
+
+    // the pending proposal went with it
+    let err = run(&mut deps, "dao", ExecuteMsg::AcceptOwner {}).unwrap_err();
+    assert!(matches!(err, ContractError::NoPendingOwner {}));
+    let err = add_chain(&mut deps, "creator").unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    let err = propose(&mut deps, "creator", "creator").unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+}
+
+#[test]
+fn renouncing_removes_the_guardian() {
+    let mut deps = setup(true, &[]);
+    let msg = ExecuteMsg::SetGuardian {
+        guardian: Some("guardian".to_string()),
+    };
+    run(&mut deps, "creator", msg).unwrap();
+
+    // nothing paused could be resumed without an owner
+    let pause = |scope| ExecuteMsg::Pause { scope };
+    let unpause = |scope| ExecuteMsg::Unpause { scope };
+    run(&mut deps, "guardian", pause(PauseScope::IbcSend)).unwrap();
+    let err = run(&mut deps, "creator", ExecuteMsg::RenounceOwnership {}).unwrap_err();
+    assert!(matches!(err, ContractError::RenounceWhilePaused {}));
+    run(&mut deps, "creator", unpause(PauseScope::IbcSend)).unwrap();
+
+    let channel = PauseScope::Channel {
+        channel: DATA_CHANNEL.to_string(),
+    };
+    run(&mut deps, "guardian", pause(channel.clone())).unwrap();
+    let err = run(&mut deps, "creator", ExecuteMsg::RenounceOwnership {}).unwrap_err();
+    assert!(matches!(err, ContractError::RenounceWhilePaused {}));
+    run(&mut deps, "creator", unpause(channel)).unwrap();
+
+    run(&mut deps, "creator", ExecuteMsg::RenounceOwnership {}).unwrap();
+    let status: PauseStatusResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PauseStatus {}).unwrap()).unwrap();
+    assert_eq!(status.guardian, None);
+    let err = run(&mut deps, "guardian", pause(PauseScope::All)).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+}
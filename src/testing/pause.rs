@@ -0,0 +1,203 @@
+use cosmwasm_std::testing::{mock_env, mock_ibc_packet_ack, mock_info};
+use cosmwasm_std::{from_binary, Uint128};
+
+use super::helpers::*;
+use crate::contract::{execute, query};
+use crate::ibc::ibc_packet_ack;
+use crate::msg::{ExecuteMsg, PauseScope, PauseStatusResponse, QueryMsg};
+use crate::{ContractError, ErrorCode};
+
+fn run(deps: &mut TestDeps, sender: &str, msg: ExecuteMsg) -> Result<(), ContractError> {
+    execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg)?;
+    Ok(())
+}
+
+fn pause(deps: &mut TestDeps, sender: &str, scope: PauseScope) -> Result<(), ContractError> {
+    run(deps, sender, ExecuteMsg::Pause { scope })
+}
+
+fn unpause(deps: &mut TestDeps, sender: &str, scope: PauseScope) -> Result<(), ContractError> {
+    run(deps, sender, ExecuteMsg::Unpause { scope })
+}
+
+fn set_guardian(deps: &mut TestDeps, guardian: &str) {
+    let msg = ExecuteMsg::SetGuardian {
+        guardian: Some(guardian.to_string()),
+    };
+    run(deps, "creator", msg).unwrap();
+}
+
+fn status(deps: &TestDeps) -> PauseStatusResponse {
+    from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PauseStatus {}).unwrap()).unwrap()
+}
+
+fn local_transfer(deps: &mut TestDeps) -> Result<(), ContractError> {
+    let msg = ExecuteMsg::Transfer {
+        recipient: "bob".to_string(),
+        amount: Uint128::new(1),
+    };
+    run(deps, "alice", msg)
+}
+
+fn data_channel() -> PauseScope {
+    PauseScope::Channel {
+        channel: DATA_CHANNEL.to_string(),
+    }
+}
+
+#[test]
+fn guardian_pauses_and_admin_unpauses() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    let err = pause(&mut deps, "guardian", PauseScope::All).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    let msg = ExecuteMsg::SetGuardian {
+        guardian: Some("guardian".to_string()),
+    };
+    let err = run(&mut deps, "alice", msg).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    set_guardian(&mut deps, "guardian");
+    assert_eq!(status(&deps).guardian.as_deref(), Some("guardian"));
+    pause(&mut deps, "guardian", PauseScope::All).unwrap();
+    assert!(status(&deps).all);
+
+    let err = unpause(&mut deps, "guardian", PauseScope::All).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    unpause(&mut deps, "creator", PauseScope::All).unwrap();
+    assert!(!status(&deps).all);
+
+    // the admin pauses too, and can dismiss the guardian
+    pause(&mut deps, "creator", PauseScope::IbcSend).unwrap();
+    run(
+        &mut deps,
+        "creator",
+        ExecuteMsg::SetGuardian { guardian: None },
+    )
+    .unwrap();
+    assert_eq!(status(&deps).guardian, None);
+    let err = pause(&mut deps, "guardian", PauseScope::All).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+}
+
+#[test]
+fn pausing_everything_stops_execution_and_receives() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 40).unwrap();
+    let in_flight = transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 10).unwrap();
+    pause(&mut deps, "creator", PauseScope::All).unwrap();
+
+    let err = local_transfer(&mut deps).unwrap_err();
+    assert!(matches!(err, ContractError::Paused {}));
+    let err = transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 10).unwrap_err();
+    assert!(matches!(err, ContractError::Paused {}));
+
+    let res = receive(
+        deps.as_mut(),
+        DATA_CHANNEL,
+        "remote",
+        transfer_packet("bob", 20),
+    );
+    let err = parse_ack(&res.acknowledgement).unwrap_err();
+    assert_eq!(err.code, ErrorCode::Paused);
+    assert_eq!(balance(&deps, "bob"), 0);
+
+    // packets already sent still settle
+    let msg =
+        mock_ibc_packet_ack(DATA_CHANNEL, &sent_packet(&in_flight), transfer_ack(10)).unwrap();
+    ibc_packet_ack(deps.as_mut(), mock_env(), msg).unwrap();
+
+    unpause(&mut deps, "creator", PauseScope::All).unwrap();
+    local_transfer(&mut deps).unwrap();
+}
+
+#[test]
+fn pausing_receives_keeps_sending() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 40).unwrap();
+    pause(&mut deps, "creator", PauseScope::IbcReceive).unwrap();
+    assert!(status(&deps).ibc_receive);
+
+    let res = receive(
+        deps.as_mut(),
+        DATA_CHANNEL,
+        "remote",
+        transfer_packet("bob", 20),
+    );
+    let err = parse_ack(&res.acknowledgement).unwrap_err();
+    assert_eq!(err.code, ErrorCode::IbcReceivePaused);
+    assert_eq!(balance(&deps, "bob"), 0);
+
+    transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 10).unwrap();
+    local_transfer(&mut deps).unwrap();
+
+    unpause(&mut deps, "creator", PauseScope::IbcReceive).unwrap();
+    let res = receive(
+        deps.as_mut(),
+        DATA_CHANNEL,
+        "remote",
+        transfer_packet("bob", 20),
+    );
+    parse_ack(&res.acknowledgement).unwrap();
+}
+
+#[test]
+fn pausing_sends_keeps_receiving() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 40).unwrap();
+    pause(&mut deps, "creator", PauseScope::IbcSend).unwrap();
+    assert!(status(&deps).ibc_send);
+
+    let err = transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 10).unwrap_err();
+    assert!(matches!(err, ContractError::IbcSendPaused {}));
+
+    let res = receive(
+        deps.as_mut(),
+        DATA_CHANNEL,
+        "remote",
+        transfer_packet("bob", 20),
+    );
+    parse_ack(&res.acknowledgement).unwrap();
+    local_transfer(&mut deps).unwrap();
+
+    unpause(&mut deps, "creator", PauseScope::IbcSend).unwrap();
+    transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 10).unwrap();
+}
+
+#[test]
+fn pausing_a_channel_leaves_the_others() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    connect(deps.as_mut(), "channel-3");
+    transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 40).unwrap();
+    pause(&mut deps, "guardian", data_channel()).unwrap_err();
+    pause(&mut deps, "creator", data_channel()).unwrap();
+    assert_eq!(status(&deps).channels, vec![DATA_CHANNEL.to_string()]);
+
+    let err = transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 10).unwrap_err();
+    assert!(matches!(err, ContractError::ChannelPaused { channel } if channel == DATA_CHANNEL));
+    let res = receive(
+        deps.as_mut(),
+        DATA_CHANNEL,
+        "remote",
+        transfer_packet("bob", 20),
+    );
+    let err = parse_ack(&res.acknowledgement).unwrap_err();
+    assert_eq!(err.code, ErrorCode::ChannelPaused);
+
+    transfer_remote(deps.as_mut(), "alice", "channel-3", 10).unwrap();
+    local_transfer(&mut deps).unwrap();
+
+    unpause(&mut deps, "creator", data_channel()).unwrap();
+    assert!(status(&deps).channels.is_empty());
+    transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 10).unwrap();
+}
+
+#[test]
+fn only_known_channels_are_paused() {
+    let mut deps = setup(true, &[]);
+    let scope = PauseScope::Channel {
+        channel: "channel-9".to_string(),
+    };
+    let err = pause(&mut deps, "creator", scope).unwrap_err();
+    assert!(matches!(err, ContractError::UnknownChannel { channel } if channel == "channel-9"));
+    assert!(status(&deps).channels.is_empty());
+}
@@ -0,0 +1,217 @@
+use cosmwasm_std::testing::{mock_env, mock_ibc_packet_timeout, mock_info};
+use cosmwasm_std::{from_binary, Env, Timestamp, Uint128};
+
+use super::helpers::*;
+use crate::contract::{execute, query};
+use crate::ibc::ibc_packet_timeout;
+use crate::msg::{ExecuteMsg, QueryMsg, RateLimitUsageResponse};
+use crate::rate_limit::{check, consume, unrecord, Flow};
+use crate::state::RateLimit;
+use crate::{ContractError, ErrorCode};
+
+fn limit(max_amount: u128, window_seconds: u64) -> Option<RateLimit> {
+    Some(RateLimit {
+        max_amount: Uint128::new(max_amount),
+        window_seconds,
+    })
+}
+
+fn set_limits(
+    deps: &mut TestDeps,
+    sender: &str,
+    channel: &str,
+    inflow: Option<RateLimit>,
+    outflow: Option<RateLimit>,
+) -> Result<(), ContractError> {
+    let msg = ExecuteMsg::SetRateLimits {
+        channel: channel.to_string(),
+        inflow,
+        outflow,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg)?;
+    Ok(())
+}
+
+fn usage(deps: &TestDeps, env: Env) -> RateLimitUsageResponse {
+    let msg = QueryMsg::RateLimitUsage {
+        channel: DATA_CHANNEL.to_string(),
+    };
+    from_binary(&query(deps.as_ref(), env, msg).unwrap()).unwrap()
+}
+
+fn env_at(seconds: u64) -> Env {
+    let mut env = mock_env();
+    env.block.time = Timestamp::from_seconds(seconds);
+    env
+}
+
+#[test]
+fn only_the_admin_sets_rate_limits() {
+    let mut deps = setup(true, &[]);
+    let err = set_limits(&mut deps, "alice", DATA_CHANNEL, limit(100, 3600), None).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    let err = set_limits(&mut deps, "creator", "channel-9", limit(100, 3600), None).unwrap_err();
+    assert!(matches!(err, ContractError::UnknownChannel { .. }));
+
+    // channels are unlimited until told otherwise
+    assert_eq!(usage(&deps, mock_env()).inflow, None);
+    set_limits(&mut deps, "creator", DATA_CHANNEL, limit(100, 3600), None).unwrap();
+    let inflow = usage(&deps, mock_env()).inflow.unwrap();
+    assert_eq!(inflow.limit, limit(100, 3600).unwrap());
+    assert_eq!(inflow.used, Uint128::zero());
+    assert_eq!(usage(&deps, mock_env()).outflow, None);
+}
+
+#[test]
+fn outflow_is_limited() {
+    let mut deps = setup(true, &[("alice", 200)]);
+    set_limits(&mut deps, "creator", DATA_CHANNEL, None, limit(100, 3600)).unwrap();
+
+    transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 60).unwrap();
+    let err = transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 50).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::RateLimitExceeded { flow, .. } if flow == "outflow"
+    ));
+    transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 40).unwrap();
+
+    let outflow = usage(&deps, mock_env()).outflow.unwrap();
+    assert_eq!(outflow.used, Uint128::new(100));
+    assert_eq!(outflow.available, Uint128::zero());
+}
+
+#[test]
+fn inflow_is_limited() {
+    let mut deps = setup(true, &[("alice", 200)]);
+    transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 200).unwrap();
+    set_limits(&mut deps, "creator", DATA_CHANNEL, limit(100, 3600), None).unwrap();
+
+    let res = receive(
+        deps.as_mut(),
+        DATA_CHANNEL,
+        "remote",
+        transfer_packet("bob", 80),
+    );
+    parse_ack(&res.acknowledgement).unwrap();
+    let res = receive(
+        deps.as_mut(),
+        DATA_CHANNEL,
+        "remote",
+        transfer_packet("bob", 30),
+    );
+    let err = parse_ack(&res.acknowledgement).unwrap_err();
+    assert_eq!(err.code, ErrorCode::RateLimitExceeded);
+    assert_eq!(balance(&deps, "bob"), 80);
+}
+
+#[test]
+fn windows_must_last() {
+    let mut deps = setup(true, &[]);
+    let err = set_limits(&mut deps, "creator", DATA_CHANNEL, limit(100, 0), None).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidRateLimit {}));
+}
+
+#[test]
+fn failed_packets_are_not_counted() {
+    let mut deps = setup(true, &[("alice", 100)]);
+    set_limits(&mut deps, "creator", DATA_CHANNEL, limit(100, 3600), None).unwrap();
+
+    // nothing is escrowed yet, the transfer fails after the limit was checked
+    let res = receive(
+        deps.as_mut(),
+        DATA_CHANNEL,
+        "remote",
+        transfer_packet("bob", 80),
+    );
+    parse_ack(&res.acknowledgement).unwrap_err();
+    assert_eq!(
+        usage(&deps, mock_env()).inflow.unwrap().used,
+        Uint128::zero()
+    );
+
+    transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 100).unwrap();
+    let res = receive(
+        deps.as_mut(),
+        DATA_CHANNEL,
+        "remote",
+        transfer_packet("bob", 80),
+    );
+    parse_ack(&res.acknowledgement).unwrap();
+    assert_eq!(
+        usage(&deps, mock_env()).inflow.unwrap().used,
+        Uint128::new(80)
+    );
+}
+
+#[test]
+fn window_rolls_instead_of_resetting() {
+    let mut deps = setup(true, &[]);
+    set_limits(&mut deps, "creator", DATA_CHANNEL, limit(100, 1000), None).unwrap();
+    let amount = Uint128::new(100);
+
+    // late in one bucket, a fixed window would reset right after
+    let env = env_at(10_090);
+    consume(&mut deps.storage, &env, DATA_CHANNEL, Flow::In, amount).unwrap();
+    for seconds in [10_100, 10_990, 11_099] {
+        let err = check(
+            &deps.storage,
+            &env_at(seconds),
+            DATA_CHANNEL,
+            Flow::In,
+            amount,
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::RateLimitExceeded { .. }));
+    }
+
+    let inflow = usage(&deps, env).inflow.unwrap();
+    assert_eq!(inflow.available, Uint128::zero());
+    assert_eq!(inflow.next_release, Some(Timestamp::from_seconds(11_100)));
+
+    check(
+        &deps.storage,
+        &env_at(11_100),
+        DATA_CHANNEL,
+        Flow::In,
+        amount,
+    )
+    .unwrap();
+}
+
+#[test]
+fn rolled_back_transfers_are_not_counted() {
+    let mut deps = setup(true, &[]);
+    set_limits(&mut deps, "creator", DATA_CHANNEL, limit(100, 1000), None).unwrap();
+    let env = mock_env();
+    let amount = Uint128::new(60);
+
+    consume(&mut deps.storage, &env, DATA_CHANNEL, Flow::In, amount).unwrap();
+    unrecord(&mut deps.storage, &env, DATA_CHANNEL, Flow::In, amount).unwrap();
+    consume(&mut deps.storage, &env, DATA_CHANNEL, Flow::In, amount).unwrap();
+}
+
+#[test]
+fn refunded_transfers_free_their_outflow() {
+    let mut deps = setup(true, &[("alice", 200)]);
+    set_limits(&mut deps, "creator", DATA_CHANNEL, None, limit(100, 1000)).unwrap();
+    let res = transfer_remote(deps.as_mut(), "alice", DATA_CHANNEL, 100).unwrap();
+
+    // the refund lands in a later block than the send
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(500);
+    let msg = mock_ibc_packet_timeout(DATA_CHANNEL, &sent_packet(&res)).unwrap();
+    ibc_packet_timeout(deps.as_mut(), env.clone(), msg).unwrap();
+
+    let outflow = usage(&deps, env.clone()).outflow.unwrap();
+    assert_eq!(outflow.used, Uint128::zero());
+    assert_eq!(outflow.available, Uint128::new(100));
+    check(
+        &deps.storage,
+        &env,
+        DATA_CHANNEL,
+        Flow::Out,
+        Uint128::new(100),
+    )
+    .unwrap();
+}